@@ -2,7 +2,7 @@
 
 use soroban_sdk::{
     contract, contractimpl, contracttype, contracterror, contractclient, symbol_short,
-    address_payload::AddressPayload, Address, BytesN, Env, Map, Vec, token,
+    address_payload::AddressPayload, xdr::ToXdr, Address, Bytes, BytesN, Env, Map, Vec, token,
 };
 
 // ---------------------------------------------------------------------------
@@ -25,6 +25,11 @@ pub enum Error {
     UnsupportedToken = 3,
     InsufficientBalance = 4,
     AgentSessionInvalid = 5,
+    WithdrawLimitExceeded = 6,
+    InsufficientAllowance = 7,
+    FeeTooHigh = 8,
+    HashChainUninitialized = 9,
+    InvalidToken = 10,
 }
 
 // ---------------------------------------------------------------------------
@@ -38,6 +43,35 @@ pub enum DataKey {
     Admin,
     Balance(Address, Address), // (user, token_sac)
     SupportedToken(Address),
+    WithdrawLimit(Address),         // token_sac -> (limit, window_ledgers)
+    WithdrawWindow(Address, Address), // (user, token_sac) -> (window_start_ledger, amount_withdrawn)
+    Allowance(Address, Address, Address), // (owner, spender, token_sac)
+    FeeConfig,
+    MinFee(Address), // token_sac -> fixed fee floor
+    CollectedFees(Address), // token_sac -> accrued fee balance
+    HashChainHead,
+    TokenDecimals(Address),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawLimitConfig {
+    pub limit: i128,
+    pub window_ledgers: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeConfig {
+    pub fee_collector: Address,
+    pub bps: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Allowance {
+    pub amount: i128,
+    pub expiration_ledger: u32,
 }
 
 // ---------------------------------------------------------------------------
@@ -81,39 +115,158 @@ fn set_balance(env: &Env, user: &Address, token_sac: &Address, amount: i128) {
     extend_persistent(env, &key);
 }
 
-fn load_zkauth_address(env: &Env) -> Address {
+fn load_allowance(env: &Env, owner: &Address, spender: &Address, token_sac: &Address) -> Allowance {
+    let key = DataKey::Allowance(owner.clone(), spender.clone(), token_sac.clone());
+    env.storage().persistent().get(&key).unwrap_or(Allowance {
+        amount: 0,
+        expiration_ledger: 0,
+    })
+}
+
+fn set_allowance(
+    env: &Env,
+    owner: &Address,
+    spender: &Address,
+    token_sac: &Address,
+    allowance: &Allowance,
+) {
+    let key = DataKey::Allowance(owner.clone(), spender.clone(), token_sac.clone());
+    env.storage().persistent().set(&key, allowance);
+    extend_persistent(env, &key);
+}
+
+/// Advances the tamper-evident operation hashchain and returns `(prev_head, new_head)`
+/// so callers can embed both in the op's event for off-chain replay verification.
+fn advance_hashchain(
+    env: &Env,
+    op_tag: &str,
+    user: &Address,
+    token_sac: &Address,
+    amount: i128,
+) -> Result<(BytesN<32>, BytesN<32>), Error> {
+    let prev_head: BytesN<32> = env
+        .storage()
+        .instance()
+        .get(&DataKey::HashChainHead)
+        .ok_or(Error::HashChainUninitialized)?;
+
+    let mut buf = Bytes::new(env);
+    buf.append(&Bytes::from_array(env, &prev_head.to_array()));
+    buf.append(&Bytes::from_slice(env, op_tag.as_bytes()));
+    buf.append(&user.to_xdr(env));
+    buf.append(&token_sac.to_xdr(env));
+    buf.append(&Bytes::from_array(env, &amount.to_be_bytes()));
+    buf.append(&Bytes::from_array(env, &env.ledger().sequence().to_be_bytes()));
+
+    let new_head: BytesN<32> = env.crypto().sha256(&buf).into();
+    env.storage().instance().set(&DataKey::HashChainHead, &new_head);
+    Ok((prev_head, new_head))
+}
+
+/// Computes the protocol fee owed on an agent-driven movement of `token_sac`, if a
+/// `FeeConfig` is set. Returns 0 when no fee is configured.
+fn compute_fee(env: &Env, token_sac: &Address, amount: i128) -> i128 {
+    let config: Option<FeeConfig> = env.storage().instance().get(&DataKey::FeeConfig);
+    let config = match config {
+        Some(c) => c,
+        None => return 0,
+    };
+    let proportional = amount * (config.bps as i128) / 10_000;
+    let min_fee: i128 = env
+        .storage()
+        .persistent()
+        .get(&DataKey::MinFee(token_sac.clone()))
+        .unwrap_or(0);
+    proportional.max(min_fee)
+}
+
+fn accrue_fee(env: &Env, token_sac: &Address, fee: i128) {
+    if fee <= 0 {
+        return;
+    }
+    let key = DataKey::CollectedFees(token_sac.clone());
+    let collected: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+    env.storage().persistent().set(&key, &(collected + fee));
+    extend_persistent(env, &key);
+}
+
+/// Enforces the admin-configured rolling withdrawal cap for `agent_withdraw`.
+/// No-op if no limit is configured for `token_sac`.
+fn check_and_record_withdraw(
+    env: &Env,
+    user: &Address,
+    token_sac: &Address,
+    amount: i128,
+) -> Result<(), Error> {
+    let limit_key = DataKey::WithdrawLimit(token_sac.clone());
+    let config: Option<WithdrawLimitConfig> = env.storage().persistent().get(&limit_key);
+    let config = match config {
+        Some(c) => c,
+        None => return Ok(()),
+    };
+    extend_persistent(env, &limit_key);
+
+    let window_key = DataKey::WithdrawWindow(user.clone(), token_sac.clone());
+    let current_ledger = env.ledger().sequence();
+    let (window_start, withdrawn): (u32, i128) = env
+        .storage()
+        .persistent()
+        .get(&window_key)
+        .unwrap_or((current_ledger, 0i128));
+
+    let (window_start, withdrawn) = if current_ledger >= window_start + config.window_ledgers {
+        (current_ledger, 0i128)
+    } else {
+        (window_start, withdrawn)
+    };
+
+    let new_withdrawn = withdrawn + amount;
+    if new_withdrawn > config.limit {
+        return Err(Error::WithdrawLimitExceeded);
+    }
+
+    env.storage()
+        .persistent()
+        .set(&window_key, &(window_start, new_withdrawn));
+    extend_persistent(env, &window_key);
+    Ok(())
+}
+
+fn load_zkauth_address(env: &Env) -> Result<Address, Error> {
     env.storage()
         .instance()
         .get(&DataKey::ZKAuthContract)
-        .unwrap_or_else(|| panic!("NotInitialized"))
+        .ok_or(Error::NotInitialized)
 }
 
-fn assert_token_supported(env: &Env, token_sac: &Address) {
+fn assert_token_supported(env: &Env, token_sac: &Address) -> Result<(), Error> {
     let key = DataKey::SupportedToken(token_sac.clone());
     let supported: bool = env.storage().persistent().get(&key).unwrap_or(false);
     if !supported {
-        panic!("UnsupportedToken");
+        return Err(Error::UnsupportedToken);
     }
     extend_persistent(env, &key);
+    Ok(())
 }
 
 /// Verifies the calling agent has a valid ZKAuth session and signed this tx.
-fn assert_agent_authorized(env: &Env, zkauth_address: &Address, user: &Address) {
+fn assert_agent_authorized(env: &Env, zkauth_address: &Address, user: &Address) -> Result<(), Error> {
     let zkauth = ZKAuthClient::new(env, zkauth_address);
 
     if !zkauth.is_session_valid(user) {
-        panic!("AgentSessionInvalid");
+        return Err(Error::AgentSessionInvalid);
     }
 
     let agent_pubkey: BytesN<32> = zkauth
         .get_agent_pubkey(user)
-        .unwrap_or_else(|| panic!("AgentSessionInvalid"));
+        .ok_or(Error::AgentSessionInvalid)?;
 
     // Convert agent Ed25519 pubkey to a Soroban Address and require its auth.
     // The agent must have signed this transaction with their keypair.
     let payload = AddressPayload::AccountIdPublicKeyEd25519(agent_pubkey);
     let agent_addr = Address::from_payload(env, payload);
     agent_addr.require_auth();
+    Ok(())
 }
 
 // ---------------------------------------------------------------------------
@@ -126,46 +279,68 @@ pub struct AgentVault;
 #[contractimpl]
 impl AgentVault {
     /// One-time init.
-    pub fn initialize(env: Env, admin: Address, zkauth_contract: Address) {
+    pub fn initialize(env: Env, admin: Address, zkauth_contract: Address) -> Result<(), Error> {
         if env.storage().instance().has(&DataKey::Admin) {
-            panic!("AlreadyInitialized");
+            return Err(Error::AlreadyInitialized);
         }
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage()
             .instance()
             .set(&DataKey::ZKAuthContract, &zkauth_contract);
+
+        // Seed the tamper-evident hashchain so genesis is pinned to this deployment's
+        // admin and ZKAuth contract.
+        let mut genesis = Bytes::new(&env);
+        genesis.append(&admin.to_xdr(&env));
+        genesis.append(&zkauth_contract.to_xdr(&env));
+        let genesis_head: BytesN<32> = env.crypto().sha256(&genesis).into();
+        env.storage()
+            .instance()
+            .set(&DataKey::HashChainHead, &genesis_head);
+
         extend_instance(&env);
+        Ok(())
     }
 
     /// Admin: whitelist a SAC token.
-    pub fn add_supported_token(env: Env, caller: Address, token_sac: Address) {
+    pub fn add_supported_token(env: Env, token_sac: Address) -> Result<(), Error> {
         extend_instance(&env);
         let admin: Address = env
             .storage()
             .instance()
             .get(&DataKey::Admin)
-            .unwrap_or_else(|| panic!("NotInitialized"));
+            .ok_or(Error::NotInitialized)?;
         admin.require_auth();
-        assert_eq!(caller, admin);
+
+        // Probe the SAC so a typo'd or non-existent address fails here with a clear
+        // error instead of silently being whitelisted and only failing at deposit time.
+        let decimals = token::Client::new(&env, &token_sac)
+            .try_decimals()
+            .map_err(|_| Error::InvalidToken)?
+            .map_err(|_| Error::InvalidToken)?;
+
+        let decimals_key = DataKey::TokenDecimals(token_sac.clone());
+        env.storage().persistent().set(&decimals_key, &decimals);
+        extend_persistent(&env, &decimals_key);
 
         let key = DataKey::SupportedToken(token_sac.clone());
         env.storage().persistent().set(&key, &true);
         extend_persistent(&env, &key);
 
         env.events()
-            .publish((symbol_short!("token"), symbol_short!("added")), token_sac);
+            .publish((symbol_short!("token"), symbol_short!("added")), (token_sac, decimals));
+        Ok(())
     }
 
     /// Admin: remove a token from the whitelist. Existing balances can still withdraw.
-    pub fn remove_supported_token(env: Env, caller: Address, token_sac: Address) {
+    pub fn remove_supported_token(env: Env, token_sac: Address) -> Result<(), Error> {
         extend_instance(&env);
         let admin: Address = env
             .storage()
             .instance()
             .get(&DataKey::Admin)
-            .unwrap_or_else(|| panic!("NotInitialized"));
+            .ok_or(Error::NotInitialized)?;
         admin.require_auth();
-        assert_eq!(caller, admin);
 
         let key = DataKey::SupportedToken(token_sac.clone());
         env.storage().persistent().set(&key, &false);
@@ -175,13 +350,14 @@ impl AgentVault {
             (symbol_short!("token"), symbol_short!("removed")),
             token_sac,
         );
+        Ok(())
     }
 
     /// User deposits a supported token.
-    pub fn deposit(env: Env, user: Address, token_sac: Address, amount: i128) {
+    pub fn deposit(env: Env, user: Address, token_sac: Address, amount: i128) -> Result<(), Error> {
         user.require_auth();
         extend_instance(&env);
-        assert_token_supported(&env, &token_sac);
+        assert_token_supported(&env, &token_sac)?;
 
         let token_client = token::Client::new(&env, &token_sac);
         token_client.transfer(&user, &env.current_contract_address(), &amount);
@@ -189,20 +365,22 @@ impl AgentVault {
         let new_balance = get_balance(&env, &user, &token_sac) + amount;
         set_balance(&env, &user, &token_sac, new_balance);
 
+        let (prev_head, new_head) = advance_hashchain(&env, "deposit", &user, &token_sac, amount)?;
         env.events().publish(
             (symbol_short!("deposit"),),
-            (user, token_sac, amount, new_balance),
+            (user, token_sac, amount, new_balance, prev_head, new_head),
         );
+        Ok(())
     }
 
     /// User withdraws their own funds.
-    pub fn withdraw(env: Env, user: Address, token_sac: Address, amount: i128) {
+    pub fn withdraw(env: Env, user: Address, token_sac: Address, amount: i128) -> Result<(), Error> {
         user.require_auth();
         extend_instance(&env);
 
         let balance = get_balance(&env, &user, &token_sac);
         if balance < amount {
-            panic!("InsufficientBalance");
+            return Err(Error::InsufficientBalance);
         }
 
         let token_client = token::Client::new(&env, &token_sac);
@@ -211,12 +389,95 @@ impl AgentVault {
         let new_balance = balance - amount;
         set_balance(&env, &user, &token_sac, new_balance);
 
+        let (prev_head, new_head) = advance_hashchain(&env, "withdraw", &user, &token_sac, amount)?;
         env.events().publish(
             (symbol_short!("withdraw"),),
-            (user, token_sac, amount, new_balance),
+            (user, token_sac, amount, new_balance, prev_head, new_head),
+        );
+        Ok(())
+    }
+
+    /// User grants `spender` the right to move up to `amount` of `token_sac` from their
+    /// balance via `transfer_from`, expiring at `expiration_ledger`. Scopes agent trust
+    /// without requiring the full ZKAuth session model.
+    pub fn approve(
+        env: Env,
+        user: Address,
+        spender: Address,
+        token_sac: Address,
+        amount: i128,
+        expiration_ledger: u32,
+    ) {
+        user.require_auth();
+        extend_instance(&env);
+
+        let allowance = Allowance {
+            amount,
+            expiration_ledger,
+        };
+        set_allowance(&env, &user, &spender, &token_sac, &allowance);
+
+        env.events().publish(
+            (symbol_short!("approve"),),
+            (user, spender, token_sac, amount, expiration_ledger),
         );
     }
 
+    /// Read-only: remaining allowance `spender` has over `owner`'s `token_sac` balance.
+    pub fn get_allowance(env: Env, owner: Address, spender: Address, token_sac: Address) -> i128 {
+        let allowance = load_allowance(&env, &owner, &spender, &token_sac);
+        if env.ledger().sequence() > allowance.expiration_ledger {
+            0
+        } else {
+            allowance.amount
+        }
+    }
+
+    /// `spender` moves up to their approved allowance of `user`'s balance to `destination`.
+    pub fn transfer_from(
+        env: Env,
+        spender: Address,
+        user: Address,
+        token_sac: Address,
+        amount: i128,
+        destination: Address,
+    ) -> Result<(), Error> {
+        spender.require_auth();
+        extend_instance(&env);
+
+        let mut allowance = load_allowance(&env, &user, &spender, &token_sac);
+        if env.ledger().sequence() > allowance.expiration_ledger {
+            return Err(Error::InsufficientAllowance);
+        }
+        let remaining_allowance = allowance
+            .amount
+            .checked_sub(amount)
+            .ok_or(Error::InsufficientAllowance)?;
+        if remaining_allowance < 0 {
+            return Err(Error::InsufficientAllowance);
+        }
+
+        let balance = get_balance(&env, &user, &token_sac);
+        if balance < amount {
+            return Err(Error::InsufficientBalance);
+        }
+
+        let token_client = token::Client::new(&env, &token_sac);
+        token_client.transfer(&env.current_contract_address(), &destination, &amount);
+
+        allowance.amount = remaining_allowance;
+        set_allowance(&env, &user, &spender, &token_sac, &allowance);
+
+        let new_balance = balance - amount;
+        set_balance(&env, &user, &token_sac, new_balance);
+
+        env.events().publish(
+            (symbol_short!("xfer_fr"),),
+            (spender, user, token_sac, amount, destination),
+        );
+        Ok(())
+    }
+
     /// Agent moves user funds to a destination (DEX, bridge, etc).
     pub fn agent_withdraw(
         env: Env,
@@ -224,42 +485,297 @@ impl AgentVault {
         token_sac: Address,
         amount: i128,
         destination: Address,
-    ) {
+    ) -> Result<(), Error> {
         extend_instance(&env);
-        let zkauth_address = load_zkauth_address(&env);
-        assert_agent_authorized(&env, &zkauth_address, &user);
+        let zkauth_address = load_zkauth_address(&env)?;
+        assert_agent_authorized(&env, &zkauth_address, &user)?;
+        check_and_record_withdraw(&env, &user, &token_sac, amount)?;
 
         let balance = get_balance(&env, &user, &token_sac);
         if balance < amount {
-            panic!("InsufficientBalance");
+            return Err(Error::InsufficientBalance);
+        }
+
+        let fee = compute_fee(&env, &token_sac, amount);
+        let net_amount = amount - fee;
+        if fee > 0 {
+            // The fee portion stays in the vault's own token balance and accrues
+            // under CollectedFees until the admin sweeps it via `withdraw_fees`.
+            accrue_fee(&env, &token_sac, fee);
         }
 
         let token_client = token::Client::new(&env, &token_sac);
-        token_client.transfer(&env.current_contract_address(), &destination, &amount);
+        token_client.transfer(&env.current_contract_address(), &destination, &net_amount);
 
         let new_balance = balance - amount;
         set_balance(&env, &user, &token_sac, new_balance);
 
+        let (prev_head, new_head) = advance_hashchain(&env, "agent_wd", &user, &token_sac, amount)?;
         env.events().publish(
             (symbol_short!("agent_wd"),),
-            (user, token_sac, amount, destination),
+            (user, token_sac, net_amount, destination, fee, prev_head, new_head),
         );
+        Ok(())
     }
 
     /// Agent returns funds after a trade settles.
     /// The agent must have already transferred tokens to this contract via SAC.
-    pub fn agent_return_funds(env: Env, user: Address, token_sac: Address, amount: i128) {
+    pub fn agent_return_funds(env: Env, user: Address, token_sac: Address, amount: i128) -> Result<(), Error> {
         extend_instance(&env);
-        let zkauth_address = load_zkauth_address(&env);
-        assert_agent_authorized(&env, &zkauth_address, &user);
+        let zkauth_address = load_zkauth_address(&env)?;
+        assert_agent_authorized(&env, &zkauth_address, &user)?;
 
-        let new_balance = get_balance(&env, &user, &token_sac) + amount;
+        let fee = compute_fee(&env, &token_sac, amount);
+        if fee > 0 {
+            accrue_fee(&env, &token_sac, fee);
+        }
+        let net_amount = amount - fee;
+
+        let new_balance = get_balance(&env, &user, &token_sac) + net_amount;
         set_balance(&env, &user, &token_sac, new_balance);
 
+        let (prev_head, new_head) = advance_hashchain(&env, "returned", &user, &token_sac, amount)?;
         env.events().publish(
             (symbol_short!("returned"),),
-            (user, token_sac, amount),
+            (user, token_sac, net_amount, fee, prev_head, new_head),
         );
+        Ok(())
+    }
+
+    /// Agent moves several (token, amount, destination) legs for a user in one call.
+    /// Every leg is validated against the resulting balance before any transfer is
+    /// performed, so the batch either commits in full or traps without partial writes.
+    pub fn agent_withdraw_batch(
+        env: Env,
+        user: Address,
+        legs: Vec<(Address, i128, Address)>,
+    ) -> Result<(), Error> {
+        extend_instance(&env);
+        let zkauth_address = load_zkauth_address(&env)?;
+        assert_agent_authorized(&env, &zkauth_address, &user)?;
+
+        // Validate every leg and compute the post-batch balance per token in memory
+        // before performing any transfer or persisting anything.
+        let mut new_balances: Map<Address, i128> = Map::new(&env);
+        for (token_sac, amount, _destination) in legs.iter() {
+            check_and_record_withdraw(&env, &user, &token_sac, amount)?;
+            let balance = new_balances
+                .get(token_sac.clone())
+                .unwrap_or_else(|| get_balance(&env, &user, &token_sac));
+            if balance < amount {
+                return Err(Error::InsufficientBalance);
+            }
+            new_balances.set(token_sac.clone(), balance - amount);
+        }
+
+        // Every leg passed validation — now canonicalize: skim the fee, transfer
+        // the net amount, persist, and advance the hashchain for each leg the
+        // same way the single-leg `agent_withdraw` does, so a batch call leaves
+        // the same fee-accrual and tamper-evident trail as N individual
+        // withdrawals.
+        let mut summary: Map<Address, i128> = Map::new(&env);
+        for (token_sac, amount, destination) in legs.iter() {
+            let fee = compute_fee(&env, &token_sac, amount);
+            let net_amount = amount - fee;
+            if fee > 0 {
+                // The fee portion stays in the vault's own token balance and
+                // accrues under CollectedFees until the admin sweeps it via
+                // `withdraw_fees`.
+                accrue_fee(&env, &token_sac, fee);
+            }
+
+            let token_client = token::Client::new(&env, &token_sac);
+            token_client.transfer(&env.current_contract_address(), &destination, &net_amount);
+            let moved = summary.get(token_sac.clone()).unwrap_or(0);
+            summary.set(token_sac.clone(), moved + net_amount);
+
+            advance_hashchain(&env, "agent_wd_batch", &user, &token_sac, amount)?;
+        }
+        for (token_sac, new_balance) in new_balances.iter() {
+            set_balance(&env, &user, &token_sac, new_balance);
+        }
+
+        let chain_head: BytesN<32> = env.storage().instance().get(&DataKey::HashChainHead).unwrap();
+        env.events().publish(
+            (symbol_short!("batch_wd"),),
+            (user, legs.len() as u32, summary, chain_head),
+        );
+        Ok(())
+    }
+
+    /// Agent returns several (token, amount) legs for a user after a trade settles.
+    pub fn agent_return_batch(
+        env: Env,
+        user: Address,
+        legs: Vec<(Address, i128)>,
+    ) -> Result<(), Error> {
+        extend_instance(&env);
+        let zkauth_address = load_zkauth_address(&env)?;
+        assert_agent_authorized(&env, &zkauth_address, &user)?;
+
+        // Skim the fee and advance the hashchain for each leg the same way the
+        // single-leg `agent_return_funds` does, so a batch call leaves the same
+        // fee-accrual and tamper-evident trail as N individual returns.
+        let mut new_balances: Map<Address, i128> = Map::new(&env);
+        for (token_sac, amount) in legs.iter() {
+            let fee = compute_fee(&env, &token_sac, amount);
+            if fee > 0 {
+                accrue_fee(&env, &token_sac, fee);
+            }
+            let net_amount = amount - fee;
+
+            let balance = new_balances
+                .get(token_sac.clone())
+                .unwrap_or_else(|| get_balance(&env, &user, &token_sac));
+            new_balances.set(token_sac.clone(), balance + net_amount);
+
+            advance_hashchain(&env, "returned_batch", &user, &token_sac, amount)?;
+        }
+
+        let mut summary: Map<Address, i128> = Map::new(&env);
+        for (token_sac, new_balance) in new_balances.iter() {
+            set_balance(&env, &user, &token_sac, new_balance);
+            summary.set(token_sac, new_balance);
+        }
+
+        let chain_head: BytesN<32> = env.storage().instance().get(&DataKey::HashChainHead).unwrap();
+        env.events().publish(
+            (symbol_short!("batch_ret"),),
+            (user, legs.len() as u32, summary, chain_head),
+        );
+        Ok(())
+    }
+
+    /// Admin: configure the protocol fee skimmed from agent-driven movements.
+    pub fn set_fee_config(
+        env: Env,
+        fee_collector: Address,
+        bps: u32,
+    ) -> Result<(), Error> {
+        extend_instance(&env);
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+
+        if bps > 10_000 {
+            return Err(Error::FeeTooHigh);
+        }
+
+        let config = FeeConfig { fee_collector, bps };
+        env.storage().instance().set(&DataKey::FeeConfig, &config);
+        Ok(())
+    }
+
+    /// Admin: set a fixed fee floor for a token; the fee charged is the larger of the
+    /// proportional fee and this floor.
+    pub fn set_min_fee(env: Env, token_sac: Address, min_fee: i128) -> Result<(), Error> {
+        extend_instance(&env);
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+
+        let key = DataKey::MinFee(token_sac);
+        env.storage().persistent().set(&key, &min_fee);
+        extend_persistent(&env, &key);
+        Ok(())
+    }
+
+    /// Admin: sweep accrued fees for a token to the configured fee collector.
+    pub fn withdraw_fees(env: Env, token_sac: Address) -> Result<i128, Error> {
+        extend_instance(&env);
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+
+        let key = DataKey::CollectedFees(token_sac.clone());
+        let collected: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        if collected > 0 {
+            let config: FeeConfig = env
+                .storage()
+                .instance()
+                .get(&DataKey::FeeConfig)
+                .ok_or(Error::NotInitialized)?;
+            let token_client = token::Client::new(&env, &token_sac);
+            token_client.transfer(&env.current_contract_address(), &config.fee_collector, &collected);
+            env.storage().persistent().set(&key, &0i128);
+        }
+        Ok(collected)
+    }
+
+    /// Admin: set a rolling withdrawal cap for agent-driven withdrawals of `token_sac`.
+    /// `limit` is denominated in whole tokens and is converted to raw stroops using the
+    /// token's own decimals so the cap means the same thing regardless of the SAC.
+    pub fn set_withdraw_limit(
+        env: Env,
+        token_sac: Address,
+        limit: i128,
+        window_ledgers: u32,
+    ) -> Result<(), Error> {
+        extend_instance(&env);
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+
+        let decimals: u32 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TokenDecimals(token_sac.clone()))
+            .unwrap_or_else(|| token::Client::new(&env, &token_sac).decimals());
+        let raw_limit = limit.saturating_mul(10i128.pow(decimals));
+
+        let key = DataKey::WithdrawLimit(token_sac.clone());
+        let config = WithdrawLimitConfig {
+            limit: raw_limit,
+            window_ledgers,
+        };
+        env.storage().persistent().set(&key, &config);
+        extend_persistent(&env, &key);
+
+        env.events().publish(
+            (symbol_short!("wd_lim"), symbol_short!("set")),
+            (token_sac, raw_limit, window_ledgers),
+        );
+        Ok(())
+    }
+
+    /// Read-only: whether `token_sac` has been whitelisted and probed successfully.
+    pub fn asset_exists(env: Env, token_sac: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::SupportedToken(token_sac))
+            .unwrap_or(false)
+    }
+
+    /// Read-only: decimals captured for `token_sac` when it was whitelisted.
+    pub fn token_decimals(env: Env, token_sac: Address) -> Option<u32> {
+        env.storage().persistent().get(&DataKey::TokenDecimals(token_sac))
+    }
+
+    /// Read-only: configured withdrawal cap for a token, in raw stroops.
+    pub fn get_withdraw_limit(env: Env, token_sac: Address) -> Option<WithdrawLimitConfig> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::WithdrawLimit(token_sac))
+    }
+
+    /// Read-only: current head of the tamper-evident operation hashchain.
+    pub fn get_hashchain_head(env: Env) -> Result<BytesN<32>, Error> {
+        env.storage()
+            .instance()
+            .get(&DataKey::HashChainHead)
+            .ok_or(Error::HashChainUninitialized)
     }
 
     /// Read-only: single balance.
@@ -287,7 +803,7 @@ impl AgentVault {
 #[cfg(test)]
 mod test {
     use super::*;
-    use soroban_sdk::testutils::Address as _;
+    use soroban_sdk::testutils::{Address as _, Ledger};
     use soroban_sdk::Env;
 
     // Mock ZKAuth contract that always returns valid session
@@ -334,7 +850,7 @@ mod test {
         let token_contract = env.register_stellar_asset_contract_v2(token_admin.clone());
         let token_sac = token_contract.address();
 
-        client.add_supported_token(&admin, &token_sac);
+        client.add_supported_token(&token_sac);
 
         (env, client, admin, token_sac, token_admin)
     }
@@ -358,7 +874,6 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "InsufficientBalance")]
     fn test_withdraw_more_than_balance() {
         let (env, client, _admin, token_sac, _token_admin) = setup_with_token();
         let user = Address::generate(&env);
@@ -367,7 +882,10 @@ mod test {
         sac_client.mint(&user, &100_0000000i128);
 
         client.deposit(&user, &token_sac, &100_0000000i128);
-        client.withdraw(&user, &token_sac, &200_0000000i128);
+        assert_eq!(
+            client.try_withdraw(&user, &token_sac, &200_0000000i128),
+            Err(Ok(Error::InsufficientBalance))
+        );
     }
 
     #[test]
@@ -386,7 +904,6 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "AgentSessionInvalid")]
     fn test_agent_withdraw_invalid_session() {
         let env = Env::default();
         env.mock_all_auths();
@@ -401,7 +918,10 @@ mod test {
         let token_sac = Address::generate(&env);
         let destination = Address::generate(&env);
 
-        client.agent_withdraw(&user, &token_sac, &100i128, &destination);
+        assert_eq!(
+            client.try_agent_withdraw(&user, &token_sac, &100i128, &destination),
+            Err(Ok(Error::AgentSessionInvalid))
+        );
     }
 
     #[test]
@@ -422,13 +942,15 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "UnsupportedToken")]
     fn test_unsupported_token_rejection() {
         let (env, client, _admin, _token_sac, _token_admin) = setup_with_token();
         let user = Address::generate(&env);
         let bad_token = Address::generate(&env);
 
-        client.deposit(&user, &bad_token, &100i128);
+        assert_eq!(
+            client.try_deposit(&user, &bad_token, &100i128),
+            Err(Ok(Error::UnsupportedToken))
+        );
     }
 
     #[test]
@@ -446,10 +968,358 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "AlreadyInitialized")]
     fn test_double_initialize() {
         let (env, client, admin, _token_sac, _token_admin) = setup_with_token();
         let zkauth = Address::generate(&env);
-        client.initialize(&admin, &zkauth);
+        assert_eq!(
+            client.try_initialize(&admin, &zkauth),
+            Err(Ok(Error::AlreadyInitialized))
+        );
+    }
+
+    #[test]
+    fn test_withdraw_limit_enforced_within_window() {
+        let (env, client, admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+        let destination = Address::generate(&env);
+
+        let sac_client = token::StellarAssetClient::new(&env, &token_sac);
+        sac_client.mint(&user, &1_000_0000000i128);
+        client.deposit(&user, &token_sac, &1_000_0000000i128);
+
+        // Cap agent withdrawals at 100 tokens per 100 ledgers.
+        client.set_withdraw_limit(&token_sac, &100i128, &100u32);
+
+        client.agent_withdraw(&user, &token_sac, &60_0000000i128, &destination);
+        assert_eq!(
+            client.try_agent_withdraw(&user, &token_sac, &60_0000000i128, &destination),
+            Err(Ok(Error::WithdrawLimitExceeded))
+        );
+    }
+
+    #[test]
+    fn test_withdraw_limit_resets_after_window() {
+        let (env, client, admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+        let destination = Address::generate(&env);
+
+        let sac_client = token::StellarAssetClient::new(&env, &token_sac);
+        sac_client.mint(&user, &1_000_0000000i128);
+        client.deposit(&user, &token_sac, &1_000_0000000i128);
+
+        client.set_withdraw_limit(&token_sac, &100i128, &100u32);
+        client.agent_withdraw(&user, &token_sac, &90_0000000i128, &destination);
+
+        env.ledger().set_sequence_number(env.ledger().sequence() + 101);
+        client.agent_withdraw(&user, &token_sac, &90_0000000i128, &destination);
+
+        assert_eq!(client.get_balance(&user, &token_sac), 820_0000000i128);
+    }
+
+    #[test]
+    fn test_transfer_from_within_allowance() {
+        let (env, client, _admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+        let spender = Address::generate(&env);
+        let destination = Address::generate(&env);
+
+        let sac_client = token::StellarAssetClient::new(&env, &token_sac);
+        sac_client.mint(&user, &1_000_0000000i128);
+        client.deposit(&user, &token_sac, &1_000_0000000i128);
+
+        client.approve(&user, &spender, &token_sac, &300_0000000i128, &1000u32);
+        assert_eq!(
+            client.get_allowance(&user, &spender, &token_sac),
+            300_0000000i128
+        );
+
+        client.transfer_from(&spender, &user, &token_sac, &200_0000000i128, &destination);
+        assert_eq!(client.get_balance(&user, &token_sac), 800_0000000i128);
+        assert_eq!(
+            client.get_allowance(&user, &spender, &token_sac),
+            100_0000000i128
+        );
+    }
+
+    #[test]
+    fn test_transfer_from_exceeds_allowance() {
+        let (env, client, _admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+        let spender = Address::generate(&env);
+        let destination = Address::generate(&env);
+
+        let sac_client = token::StellarAssetClient::new(&env, &token_sac);
+        sac_client.mint(&user, &1_000_0000000i128);
+        client.deposit(&user, &token_sac, &1_000_0000000i128);
+
+        client.approve(&user, &spender, &token_sac, &100_0000000i128, &1000u32);
+        assert_eq!(
+            client.try_transfer_from(&spender, &user, &token_sac, &200_0000000i128, &destination),
+            Err(Ok(Error::InsufficientAllowance))
+        );
+    }
+
+    #[test]
+    fn test_transfer_from_after_expiration() {
+        let (env, client, _admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+        let spender = Address::generate(&env);
+        let destination = Address::generate(&env);
+
+        let sac_client = token::StellarAssetClient::new(&env, &token_sac);
+        sac_client.mint(&user, &1_000_0000000i128);
+        client.deposit(&user, &token_sac, &1_000_0000000i128);
+
+        let expiration = env.ledger().sequence() + 10;
+        client.approve(&user, &spender, &token_sac, &500_0000000i128, &expiration);
+
+        env.ledger().set_sequence_number(expiration + 1);
+        assert_eq!(client.get_allowance(&user, &spender, &token_sac), 0);
+        assert_eq!(
+            client.try_transfer_from(&spender, &user, &token_sac, &100_0000000i128, &destination),
+            Err(Ok(Error::InsufficientAllowance))
+        );
+    }
+
+    #[test]
+    fn test_agent_withdraw_batch_multi_token() {
+        let (env, client, admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+        let destination = Address::generate(&env);
+
+        let coll_admin = Address::generate(&env);
+        let token_b_contract = env.register_stellar_asset_contract_v2(coll_admin);
+        let token_b = token_b_contract.address();
+        client.add_supported_token(&token_b);
+
+        let sac_a = token::StellarAssetClient::new(&env, &token_sac);
+        let sac_b = token::StellarAssetClient::new(&env, &token_b);
+        sac_a.mint(&user, &1_000_0000000i128);
+        sac_b.mint(&user, &1_000_0000000i128);
+        client.deposit(&user, &token_sac, &1_000_0000000i128);
+        client.deposit(&user, &token_b, &1_000_0000000i128);
+
+        let legs = soroban_sdk::vec![
+            &env,
+            (token_sac.clone(), 100_0000000i128, destination.clone()),
+            (token_b.clone(), 200_0000000i128, destination.clone()),
+        ];
+        client.agent_withdraw_batch(&user, &legs);
+
+        assert_eq!(client.get_balance(&user, &token_sac), 900_0000000i128);
+        assert_eq!(client.get_balance(&user, &token_b), 800_0000000i128);
+    }
+
+    #[test]
+    fn test_agent_withdraw_batch_all_or_nothing() {
+        let (env, client, admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+        let destination = Address::generate(&env);
+
+        let coll_admin = Address::generate(&env);
+        let token_b_contract = env.register_stellar_asset_contract_v2(coll_admin);
+        let token_b = token_b_contract.address();
+        client.add_supported_token(&token_b);
+
+        let sac_a = token::StellarAssetClient::new(&env, &token_sac);
+        sac_a.mint(&user, &100_0000000i128);
+        client.deposit(&user, &token_sac, &100_0000000i128);
+        // token_b is supported but user never deposited, so the second leg must fail
+        // and the whole batch (including the first, otherwise-valid leg) must revert.
+
+        let legs = soroban_sdk::vec![
+            &env,
+            (token_sac.clone(), 50_0000000i128, destination.clone()),
+            (token_b.clone(), 50_0000000i128, destination.clone()),
+        ];
+        assert_eq!(
+            client.try_agent_withdraw_batch(&user, &legs),
+            Err(Ok(Error::InsufficientBalance))
+        );
+        // First leg must not have been applied despite passing its own check.
+        assert_eq!(client.get_balance(&user, &token_sac), 100_0000000i128);
+    }
+
+    #[test]
+    fn test_agent_return_batch() {
+        let (env, client, admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+
+        let coll_admin = Address::generate(&env);
+        let token_b_contract = env.register_stellar_asset_contract_v2(coll_admin);
+        let token_b = token_b_contract.address();
+        client.add_supported_token(&token_b);
+
+        let legs = soroban_sdk::vec![
+            &env,
+            (token_sac.clone(), 100_0000000i128),
+            (token_b.clone(), 200_0000000i128),
+        ];
+        client.agent_return_batch(&user, &legs);
+
+        assert_eq!(client.get_balance(&user, &token_sac), 100_0000000i128);
+        assert_eq!(client.get_balance(&user, &token_b), 200_0000000i128);
+    }
+
+    #[test]
+    fn test_agent_withdraw_batch_advances_hashchain() {
+        // Regression test: batch withdrawals must leave the same tamper-evident
+        // trail as the equivalent single-leg `agent_withdraw` calls, not be
+        // invisible to the hashchain.
+        let (env, client, _admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+        let destination = Address::generate(&env);
+
+        let sac_client = token::StellarAssetClient::new(&env, &token_sac);
+        sac_client.mint(&user, &1_000_0000000i128);
+        client.deposit(&user, &token_sac, &1_000_0000000i128);
+
+        let before = client.get_hashchain_head();
+
+        let legs = soroban_sdk::vec![&env, (token_sac.clone(), 100_0000000i128, destination.clone())];
+        client.agent_withdraw_batch(&user, &legs);
+
+        assert_ne!(before, client.get_hashchain_head());
+    }
+
+    #[test]
+    fn test_agent_return_batch_advances_hashchain() {
+        // Regression test: batch returns must leave the same tamper-evident
+        // trail as the equivalent single-leg `agent_return_funds` calls.
+        let (env, client, _admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+
+        let before = client.get_hashchain_head();
+
+        let legs = soroban_sdk::vec![&env, (token_sac.clone(), 100_0000000i128)];
+        client.agent_return_batch(&user, &legs);
+
+        assert_ne!(before, client.get_hashchain_head());
+    }
+
+    #[test]
+    fn test_agent_withdraw_fee_skimmed_and_swept() {
+        let (env, client, admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+        let destination = Address::generate(&env);
+        let fee_collector = Address::generate(&env);
+
+        let sac_client = token::StellarAssetClient::new(&env, &token_sac);
+        sac_client.mint(&user, &1_000_0000000i128);
+        client.deposit(&user, &token_sac, &1_000_0000000i128);
+
+        // 1% fee
+        client.set_fee_config(&fee_collector, &100u32);
+        client.agent_withdraw(&user, &token_sac, &100_0000000i128, &destination);
+
+        let token_client = token::Client::new(&env, &token_sac);
+        assert_eq!(token_client.balance(&destination), 99_0000000i128);
+        assert_eq!(client.get_balance(&user, &token_sac), 900_0000000i128);
+
+        let swept = client.withdraw_fees(&token_sac);
+        assert_eq!(swept, 1_0000000i128);
+        assert_eq!(token_client.balance(&fee_collector), 1_0000000i128);
+    }
+
+    #[test]
+    fn test_agent_withdraw_batch_fee_skimmed_and_swept() {
+        // Regression test: batch withdrawals must skim the same protocol fee
+        // as the equivalent single-leg `agent_withdraw` calls, not let an
+        // agent bypass it by routing through the batch endpoint.
+        let (env, client, _admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+        let destination = Address::generate(&env);
+        let fee_collector = Address::generate(&env);
+
+        let sac_client = token::StellarAssetClient::new(&env, &token_sac);
+        sac_client.mint(&user, &1_000_0000000i128);
+        client.deposit(&user, &token_sac, &1_000_0000000i128);
+
+        // 1% fee
+        client.set_fee_config(&fee_collector, &100u32);
+        let legs = soroban_sdk::vec![&env, (token_sac.clone(), 100_0000000i128, destination.clone())];
+        client.agent_withdraw_batch(&user, &legs);
+
+        let token_client = token::Client::new(&env, &token_sac);
+        assert_eq!(token_client.balance(&destination), 99_0000000i128);
+        assert_eq!(client.get_balance(&user, &token_sac), 900_0000000i128);
+
+        let swept = client.withdraw_fees(&token_sac);
+        assert_eq!(swept, 1_0000000i128);
+        assert_eq!(token_client.balance(&fee_collector), 1_0000000i128);
+    }
+
+    #[test]
+    fn test_agent_return_batch_fee_skimmed_and_swept() {
+        // Regression test: batch returns must skim the same protocol fee as
+        // the equivalent single-leg `agent_return_funds` calls.
+        let (env, client, _admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+        let fee_collector = Address::generate(&env);
+
+        // The agent has already transferred the returned tokens to the vault,
+        // so the skimmed fee is actually held there when it is swept.
+        let sac_client = token::StellarAssetClient::new(&env, &token_sac);
+        sac_client.mint(&client.address, &100_0000000i128);
+
+        // 1% fee
+        client.set_fee_config(&fee_collector, &100u32);
+        let legs = soroban_sdk::vec![&env, (token_sac.clone(), 100_0000000i128)];
+        client.agent_return_batch(&user, &legs);
+
+        assert_eq!(client.get_balance(&user, &token_sac), 99_0000000i128);
+
+        let swept = client.withdraw_fees(&token_sac);
+        assert_eq!(swept, 1_0000000i128);
+    }
+
+    #[test]
+    fn test_set_fee_config_rejects_fee_too_high() {
+        let (env, client, admin, _token_sac, _token_admin) = setup_with_token();
+        let fee_collector = Address::generate(&env);
+        assert_eq!(
+            client.try_set_fee_config(&fee_collector, &10_001u32),
+            Err(Ok(Error::FeeTooHigh))
+        );
+    }
+
+    #[test]
+    fn test_hashchain_advances_and_is_order_dependent() {
+        let (env, client, _admin, token_sac, _token_admin) = setup_with_token();
+        let user = Address::generate(&env);
+
+        let genesis_head = client.get_hashchain_head();
+
+        let sac_client = token::StellarAssetClient::new(&env, &token_sac);
+        sac_client.mint(&user, &1_000_0000000i128);
+        client.deposit(&user, &token_sac, &500_0000000i128);
+        let after_deposit = client.get_hashchain_head();
+        assert_ne!(genesis_head, after_deposit);
+
+        client.withdraw(&user, &token_sac, &100_0000000i128);
+        let after_withdraw = client.get_hashchain_head();
+        assert_ne!(after_deposit, after_withdraw);
+    }
+
+    #[test]
+    fn test_add_supported_token_rejects_nonexistent_sac() {
+        let (env, client, admin, _token_sac, _token_admin) = setup_with_token();
+        let bad_token = Address::generate(&env);
+        assert_eq!(
+            client.try_add_supported_token(&bad_token),
+            Err(Ok(Error::InvalidToken))
+        );
+    }
+
+    #[test]
+    fn test_add_supported_token_captures_decimals() {
+        let (env, client, admin, _token_sac, _token_admin) = setup_with_token();
+        let coll_admin = Address::generate(&env);
+        let new_token = env.register_stellar_asset_contract_v2(coll_admin).address();
+
+        assert!(!client.asset_exists(&new_token));
+        client.add_supported_token(&new_token);
+        assert!(client.asset_exists(&new_token));
+        assert_eq!(client.token_decimals(&new_token), Some(7u32));
     }
 }
@@ -2,7 +2,7 @@
 
 use soroban_sdk::{
     contract, contractimpl, contracttype, contracterror, contractclient, symbol_short,
-    address_payload::AddressPayload, Address, BytesN, Env, Symbol, token,
+    address_payload::AddressPayload, Address, BytesN, Env, Map, Symbol, Vec, token,
 };
 
 // ---------------------------------------------------------------------------
@@ -14,6 +14,9 @@ const INSTANCE_BUMP: u32 = 518400;
 const HEALTH_SCALAR: i128 = 10_000; // health ratio scaled, 10000 = 1.0
 const INTEREST_PERIOD: u32 = 1000; // ledgers between interest accrual periods
 const PRICE_SCALAR: i128 = 10_000_000; // Stellar 7-decimal precision
+const LIQUIDATION_CLOSE_FACTOR_BPS: i128 = 5000; // a liquidator may repay at most 50% of debt per call
+const LIQUIDATION_CLOSE_AMOUNT: i128 = 2; // dust threshold below which full (100%) closure is allowed
+const WAD: i128 = 1_000_000_000_000; // fixed-point scale for CumulativeBorrowRate, starts at 1 WAD
 
 // ---------------------------------------------------------------------------
 // Errors
@@ -37,6 +40,11 @@ pub enum Error {
     AgentSessionInvalid = 12,
     OracleCallFailed = 13,
     DivisionByZero = 14,
+    InvalidRepayAmount = 15,
+    CollateralCapExceeded = 16,
+    PoolLiquidityCapExceeded = 17,
+    StalePrice = 18,
+    PriceDeviationTooLarge = 19,
 }
 
 // ---------------------------------------------------------------------------
@@ -54,10 +62,12 @@ pub enum PositionDirection {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Position {
     pub borrowed_amount: i128,
-    pub collateral_token: Address,
-    pub collateral_amount: i128,
+    /// Basket of locked collateral, following the Solend/Port obligation
+    /// model: a trader can back one position with deposits across several
+    /// token types instead of exactly one.
+    pub collateral: Map<Address, i128>,
     pub opened_at_ledger: u32,
-    pub last_interest_ledger: u32,
+    pub borrow_index_snapshot: i128,
     pub direction: PositionDirection,
 }
 
@@ -67,6 +77,14 @@ pub struct CollateralConfig {
     pub collateral_factor_bps: u32,
     pub price_feed_key: Symbol,
     pub is_active: bool,
+    pub supply_cap: i128,
+    /// Collateral factor the admin is easing towards, applied gradually over
+    /// `[start_ledger, end_ledger]` instead of instantly (see
+    /// `effective_collateral_factor`). Set equal to `collateral_factor_bps`
+    /// with `start_ledger == end_ledger` for "no transition scheduled".
+    pub target_collateral_factor_bps: u32,
+    pub start_ledger: u32,
+    pub end_ledger: u32,
 }
 
 #[contracttype]
@@ -104,10 +122,21 @@ pub enum DataKey {
     TraderPosition(Address),
     CollateralBalance(Address, Address), // (trader, token)
     CollateralConfig(Address),
-    BorrowRateBps,
+    CollateralTotalDeposited(Address), // token -> aggregate deposited across all users
+    OptimalUtilizationBps,
+    BaseRateBps,
+    Slope1Bps,
+    Slope2Bps,
+    CumulativeBorrowRate,
+    LastAccrualLedger,
     LiquidationBonusBps,
     MaxLeverageBps,
     MinHealthBps,
+    MaxTotalLiquidity,
+    MaxPriceAgeSecs,
+    MaxDeviationBps,
+    LastKnownPrice(Symbol), // price_feed_key -> last accepted PriceData
+    DexPool(Address),       // collateral token -> AMM/order-book contract used to simulate sales
 }
 
 // ---------------------------------------------------------------------------
@@ -125,6 +154,17 @@ pub trait OracleInterface {
     fn lastprice(env: Env, asset: Symbol) -> Option<PriceData>;
 }
 
+/// A DEX/AMM liquidity source a collateral's sale can be simulated against,
+/// following the SPL lending `TradeSimulator` idea: walk the pool's actual
+/// depth instead of assuming the oracle mid fills at any size.
+#[contractclient(name = "DexPoolClient")]
+pub trait DexPoolInterface {
+    /// Returns the realizable output (in the quote/borrowed asset, at
+    /// `PRICE_SCALAR`-normalized precision) of selling `amount_in` of the
+    /// pool's base asset, walking successive price levels until filled.
+    fn simulate_sell(env: Env, amount_in: i128) -> i128;
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
@@ -163,48 +203,231 @@ fn assert_agent_authorized(env: &Env, zkauth_address: &Address, user: &Address)
     agent_addr.require_auth();
 }
 
+/// Reads the latest price for `price_feed_key`, guarding against a frozen
+/// feed and against a single anomalous tick (Mango v4-style price band):
+/// the reading is rejected if it is older than `MaxPriceAgeSecs`, or if it
+/// deviates from the last accepted price (while that price is still itself
+/// within the staleness window) by more than `MaxDeviationBps`.
 fn get_oracle_price(env: &Env, oracle_address: &Address, price_feed_key: &Symbol) -> i128 {
     let client = OracleClient::new(env, oracle_address);
     let price_data = client
         .lastprice(price_feed_key)
         .unwrap_or_else(|| panic!("OracleCallFailed"));
+
+    let max_price_age_secs: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::MaxPriceAgeSecs)
+        .unwrap();
+    let now = env.ledger().timestamp();
+    if now.saturating_sub(price_data.timestamp) > max_price_age_secs as u64 {
+        panic!("StalePrice");
+    }
+
+    let last_key = DataKey::LastKnownPrice(price_feed_key.clone());
+    if let Some(last) = env.storage().persistent().get::<_, PriceData>(&last_key) {
+        if last.price != 0 && now.saturating_sub(last.timestamp) <= max_price_age_secs as u64 {
+            let max_deviation_bps: u32 = env
+                .storage()
+                .instance()
+                .get(&DataKey::MaxDeviationBps)
+                .unwrap();
+            let deviation_bps = (price_data.price - last.price).abs() * 10_000 / last.price.abs();
+            if deviation_bps > max_deviation_bps as i128 {
+                panic!("PriceDeviationTooLarge");
+            }
+        }
+    }
+    env.storage().persistent().set(&last_key, &price_data);
+    extend_persistent(env, &last_key);
+
     price_data.price
 }
 
-fn compute_health(
-    collateral_amount: i128,
-    collateral_price: i128,
-    collateral_factor_bps: u32,
+/// Realizable sale proceeds for `amount` of `token`, in the same
+/// `PRICE_SCALAR`-normalized units as `amount * oracle_price / PRICE_SCALAR`.
+/// Walks the configured DEX pool's depth when one is set (`set_dex_pool`) so
+/// a position large relative to available liquidity is valued with
+/// slippage instead of the oracle mid; falls back to the oracle price when
+/// no pool is configured for the token.
+fn realizable_sale_proceeds(env: &Env, token: &Address, amount: i128, oracle_price: i128) -> i128 {
+    let dex_key = DataKey::DexPool(token.clone());
+    if let Some(pool) = env.storage().instance().get::<_, Address>(&dex_key) {
+        let dex_client = DexPoolClient::new(env, &pool);
+        return dex_client.simulate_sell(&amount);
+    }
+    amount * oracle_price / PRICE_SCALAR
+}
+
+/// Risk-weighted value of one deposit's realizable sale proceeds:
+/// `proceeds * factor / 10000`.
+fn collateral_value(proceeds: i128, collateral_factor_bps: u32) -> i128 {
+    proceeds * (collateral_factor_bps as i128) / 10_000
+}
+
+fn health_from_collateral_value(total_collateral_value: i128, borrowed_amount: i128) -> i128 {
+    if borrowed_amount == 0 {
+        return i128::MAX;
+    }
+    total_collateral_value * HEALTH_SCALAR / borrowed_amount
+}
+
+/// Aggregate health across every deposit in a basket (Solend/Port obligation
+/// model): each deposit's risk-weighted value is summed before dividing by
+/// the position's total debt, so a diversified basket nets out its risk
+/// instead of being judged one token at a time.
+fn compute_basket_health(
+    env: &Env,
+    oracle_address: &Address,
+    collateral: &Map<Address, i128>,
     borrowed_amount: i128,
 ) -> i128 {
     if borrowed_amount == 0 {
         return i128::MAX;
     }
-    // health = (collateral_amount * price * factor / 10000) / borrowed_amount
-    // scaled by HEALTH_SCALAR
-    let collateral_value =
-        collateral_amount * collateral_price * (collateral_factor_bps as i128)
-            / (10_000 * PRICE_SCALAR);
-    collateral_value * HEALTH_SCALAR / borrowed_amount
+    let current_ledger = env.ledger().sequence();
+    let mut total_value = 0i128;
+    for (token, amount) in collateral.iter() {
+        let config = load_collateral_config(env, &token);
+        let price = get_oracle_price(env, oracle_address, &config.price_feed_key);
+        let factor = effective_collateral_factor(&config, current_ledger);
+        let proceeds = realizable_sale_proceeds(env, &token, amount, price);
+        total_value += collateral_value(proceeds, factor);
+    }
+    health_from_collateral_value(total_value, borrowed_amount)
 }
 
-fn accrue_interest_internal(
-    position: &mut Position,
-    borrow_rate_bps: u32,
-    current_ledger: u32,
-) -> i128 {
-    let elapsed = current_ledger.saturating_sub(position.last_interest_ledger);
+/// Linearly interpolates a collateral's effective factor between
+/// `collateral_factor_bps` and `target_collateral_factor_bps` over
+/// `[start_ledger, end_ledger]`, following Mango v4's "changing maint
+/// weights over time": clamped to the current factor before the window
+/// opens and to the target factor once it closes, so admins can tighten
+/// risk parameters smoothly instead of with a step function.
+fn effective_collateral_factor(config: &CollateralConfig, current_ledger: u32) -> u32 {
+    if config.end_ledger <= config.start_ledger || current_ledger <= config.start_ledger {
+        return config.collateral_factor_bps;
+    }
+    if current_ledger >= config.end_ledger {
+        return config.target_collateral_factor_bps;
+    }
+    let start = config.collateral_factor_bps as i128;
+    let target = config.target_collateral_factor_bps as i128;
+    let elapsed = (current_ledger - config.start_ledger) as i128;
+    let window = (config.end_ledger - config.start_ledger) as i128;
+    (start + (target - start) * elapsed / window) as u32
+}
+
+/// Two-slope utilization curve, following the Port/Solend reserve rate model:
+/// flat-ish below `optimal_utilization_bps`, steep above it.
+#[allow(clippy::too_many_arguments)]
+fn compute_borrow_rate_bps(
+    utilization_bps: u32,
+    optimal_utilization_bps: u32,
+    base_rate_bps: u32,
+    slope1_bps: u32,
+    slope2_bps: u32,
+) -> u32 {
+    let utilization = utilization_bps as i128;
+    let optimal = optimal_utilization_bps as i128;
+    let base = base_rate_bps as i128;
+    let slope1 = slope1_bps as i128;
+    let slope2 = slope2_bps as i128;
+
+    if optimal == 0 {
+        // Degenerate config: treat everything as "above optimal".
+        return (base + slope1 + slope2) as u32;
+    }
+
+    let rate = if utilization <= optimal {
+        base + slope1 * utilization / optimal
+    } else {
+        let excess = (utilization - optimal) * 10_000 / (10_000 - optimal);
+        base + slope1 + slope2 * excess / 10_000
+    };
+    rate as u32
+}
+
+fn current_utilization_bps(total_borrowed: i128, total_liquidity: i128) -> u32 {
+    if total_liquidity <= 0 {
+        0
+    } else {
+        (total_borrowed * 10_000 / total_liquidity) as u32
+    }
+}
+
+fn current_borrow_rate_bps(env: &Env, total_borrowed: i128, total_liquidity: i128) -> u32 {
+    let optimal: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::OptimalUtilizationBps)
+        .unwrap();
+    let base: u32 = env.storage().instance().get(&DataKey::BaseRateBps).unwrap();
+    let slope1: u32 = env.storage().instance().get(&DataKey::Slope1Bps).unwrap();
+    let slope2: u32 = env.storage().instance().get(&DataKey::Slope2Bps).unwrap();
+
+    let utilization = current_utilization_bps(total_borrowed, total_liquidity);
+    compute_borrow_rate_bps(utilization, optimal, base, slope1, slope2)
+}
+
+/// Projects the global `CumulativeBorrowRate` index forward to the current
+/// ledger without persisting it, following the Solend/Port obligation model:
+/// `index *= (1 + rate_per_period * elapsed)`.
+fn peek_cumulative_index(env: &Env, total_borrowed: i128, total_liquidity: i128) -> i128 {
+    let current_ledger = env.ledger().sequence();
+    let last_ledger: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::LastAccrualLedger)
+        .unwrap_or(current_ledger);
+    let index: i128 = env
+        .storage()
+        .instance()
+        .get(&DataKey::CumulativeBorrowRate)
+        .unwrap_or(WAD);
+
+    let elapsed = current_ledger.saturating_sub(last_ledger);
     if elapsed == 0 {
-        return 0;
+        return index;
     }
-    // interest = borrowed * rate_bps * elapsed / (10000 * INTEREST_PERIOD)
-    let interest = position.borrowed_amount * (borrow_rate_bps as i128) * (elapsed as i128)
-        / (10_000 * INTEREST_PERIOD as i128);
-    position.borrowed_amount += interest;
-    position.last_interest_ledger = current_ledger;
+
+    let rate_bps = current_borrow_rate_bps(env, total_borrowed, total_liquidity);
+    let growth_bps = (rate_bps as i128) * (elapsed as i128) / (INTEREST_PERIOD as i128);
+    index + index * growth_bps / 10_000
+}
+
+/// Advances and persists the global cumulative borrow index. Called on every
+/// accrual-triggering interaction so interest compounds uniformly across all
+/// positions instead of drifting based on when each one was last touched.
+fn advance_cumulative_index(env: &Env, total_borrowed: i128, total_liquidity: i128) -> i128 {
+    let new_index = peek_cumulative_index(env, total_borrowed, total_liquidity);
+    env.storage()
+        .instance()
+        .set(&DataKey::CumulativeBorrowRate, &new_index);
+    env.storage()
+        .instance()
+        .set(&DataKey::LastAccrualLedger, &env.ledger().sequence());
+    new_index
+}
+
+/// Realizes a position's true (index-adjusted) debt into `borrowed_amount`
+/// and resets its snapshot to `current_index`. Returns the interest applied.
+fn realize_position_debt(position: &mut Position, current_index: i128) -> i128 {
+    let true_debt = position.borrowed_amount * current_index / position.borrow_index_snapshot;
+    let interest = true_debt - position.borrowed_amount;
+    position.borrowed_amount = true_debt;
+    position.borrow_index_snapshot = current_index;
     interest
 }
 
+/// Read-only projection of a position's current debt, without persisting
+/// any index advance.
+fn current_position_debt(env: &Env, position: &Position) -> i128 {
+    let total_liquidity = get_i128(env, &DataKey::TotalLiquidity);
+    let total_borrowed = get_i128(env, &DataKey::TotalBorrowed);
+    let current_index = peek_cumulative_index(env, total_borrowed, total_liquidity);
+    position.borrowed_amount * current_index / position.borrow_index_snapshot
+}
+
 fn load_collateral_balance(env: &Env, user: &Address, token: &Address) -> i128 {
     let key = DataKey::CollateralBalance(user.clone(), token.clone());
     let bal = env.storage().persistent().get(&key).unwrap_or(0i128);
@@ -220,6 +443,22 @@ fn set_collateral_balance(env: &Env, user: &Address, token: &Address, amount: i1
     extend_persistent(env, &key);
 }
 
+/// If `user` has an open position whose basket already includes `token`,
+/// updates that leg's locked amount to `new_balance` so the position's
+/// snapshot never drifts from the live `CollateralBalance`. A no-op for
+/// tokens not part of the basket (e.g. free collateral never locked by
+/// `open_position`) and for users with no open position.
+fn sync_position_collateral_leg(env: &Env, user: &Address, token: &Address, new_balance: i128) {
+    let pos_key = DataKey::TraderPosition(user.clone());
+    if let Some(mut position) = env.storage().persistent().get::<_, Position>(&pos_key) {
+        if position.collateral.get(token.clone()).is_some() {
+            position.collateral.set(token.clone(), new_balance);
+            env.storage().persistent().set(&pos_key, &position);
+            extend_persistent(env, &pos_key);
+        }
+    }
+}
+
 fn load_collateral_config(env: &Env, token: &Address) -> CollateralConfig {
     let key = DataKey::CollateralConfig(token.clone());
     env.storage()
@@ -228,6 +467,17 @@ fn load_collateral_config(env: &Env, token: &Address) -> CollateralConfig {
         .unwrap_or_else(|| panic!("UnsupportedCollateral"))
 }
 
+fn load_collateral_total_deposited(env: &Env, token: &Address) -> i128 {
+    let key = DataKey::CollateralTotalDeposited(token.clone());
+    env.storage().persistent().get(&key).unwrap_or(0i128)
+}
+
+fn set_collateral_total_deposited(env: &Env, token: &Address, amount: i128) {
+    let key = DataKey::CollateralTotalDeposited(token.clone());
+    env.storage().persistent().set(&key, &amount);
+    extend_persistent(env, &key);
+}
+
 // ---------------------------------------------------------------------------
 // Contract
 // ---------------------------------------------------------------------------
@@ -244,10 +494,16 @@ impl LeveragePool {
         pool_asset: Address,
         oracle_contract: Address,
         zkauth_contract: Address,
-        borrow_rate_bps: u32,
+        optimal_utilization_bps: u32,
+        base_rate_bps: u32,
+        slope1_bps: u32,
+        slope2_bps: u32,
         liquidation_bonus_bps: u32,
         max_leverage_bps: u32,
         min_health_bps: u32,
+        max_total_liquidity: i128,
+        max_price_age_secs: u32,
+        max_deviation_bps: u32,
     ) {
         if env.storage().instance().has(&DataKey::Admin) {
             panic!("AlreadyInitialized");
@@ -264,7 +520,16 @@ impl LeveragePool {
             .set(&DataKey::ZKAuthContract, &zkauth_contract);
         env.storage()
             .instance()
-            .set(&DataKey::BorrowRateBps, &borrow_rate_bps);
+            .set(&DataKey::OptimalUtilizationBps, &optimal_utilization_bps);
+        env.storage()
+            .instance()
+            .set(&DataKey::BaseRateBps, &base_rate_bps);
+        env.storage()
+            .instance()
+            .set(&DataKey::Slope1Bps, &slope1_bps);
+        env.storage()
+            .instance()
+            .set(&DataKey::Slope2Bps, &slope2_bps);
         env.storage()
             .instance()
             .set(&DataKey::LiquidationBonusBps, &liquidation_bonus_bps);
@@ -274,11 +539,27 @@ impl LeveragePool {
         env.storage()
             .instance()
             .set(&DataKey::MinHealthBps, &min_health_bps);
+        env.storage()
+            .instance()
+            .set(&DataKey::MaxTotalLiquidity, &max_total_liquidity);
+        env.storage()
+            .instance()
+            .set(&DataKey::MaxPriceAgeSecs, &max_price_age_secs);
+        env.storage()
+            .instance()
+            .set(&DataKey::MaxDeviationBps, &max_deviation_bps);
 
         set_i128(&env, &DataKey::TotalLiquidity, 0);
         set_i128(&env, &DataKey::TotalBorrowed, 0);
         set_i128(&env, &DataKey::TotalShares, 0);
 
+        env.storage()
+            .instance()
+            .set(&DataKey::CumulativeBorrowRate, &WAD);
+        env.storage()
+            .instance()
+            .set(&DataKey::LastAccrualLedger, &env.ledger().sequence());
+
         extend_instance(&env);
     }
 
@@ -306,12 +587,80 @@ impl LeveragePool {
             .publish((symbol_short!("coll"), symbol_short!("set")), token);
     }
 
+    /// Admin: point a collateral token at a DEX/AMM pool contract used to
+    /// simulate sale proceeds for health and liquidation valuation, instead
+    /// of relying solely on the oracle mid (see `realizable_sale_proceeds`).
+    pub fn set_dex_pool(env: Env, caller: Address, token: Address, pool: Address) {
+        extend_instance(&env);
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .unwrap_or_else(|| panic!("NotInitialized"));
+        admin.require_auth();
+        assert_eq!(caller, admin);
+
+        env.storage()
+            .instance()
+            .set(&DataKey::DexPool(token.clone()), &pool);
+
+        env.events()
+            .publish((symbol_short!("dex"), symbol_short!("set")), token);
+    }
+
+    /// Admin: reconfigure the kinked utilization/borrow-rate curve.
+    pub fn set_rate_curve(
+        env: Env,
+        caller: Address,
+        optimal_utilization_bps: u32,
+        base_rate_bps: u32,
+        slope1_bps: u32,
+        slope2_bps: u32,
+    ) {
+        extend_instance(&env);
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .unwrap_or_else(|| panic!("NotInitialized"));
+        admin.require_auth();
+        assert_eq!(caller, admin);
+
+        env.storage()
+            .instance()
+            .set(&DataKey::OptimalUtilizationBps, &optimal_utilization_bps);
+        env.storage()
+            .instance()
+            .set(&DataKey::BaseRateBps, &base_rate_bps);
+        env.storage()
+            .instance()
+            .set(&DataKey::Slope1Bps, &slope1_bps);
+        env.storage()
+            .instance()
+            .set(&DataKey::Slope2Bps, &slope2_bps);
+
+        env.events()
+            .publish((symbol_short!("rate"), symbol_short!("set")), ());
+    }
+
     // ----- LP functions -----
 
     pub fn lp_deposit(env: Env, lp: Address, amount: i128) {
         lp.require_auth();
         extend_instance(&env);
 
+        let total_shares = get_i128(&env, &DataKey::TotalShares);
+        let total_liquidity = get_i128(&env, &DataKey::TotalLiquidity);
+
+        let max_total_liquidity: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::MaxTotalLiquidity)
+            .unwrap();
+        if total_liquidity + amount > max_total_liquidity {
+            panic!("PoolLiquidityCapExceeded");
+        }
+
         let pool_asset: Address = env
             .storage()
             .instance()
@@ -320,9 +669,6 @@ impl LeveragePool {
         let token_client = token::Client::new(&env, &pool_asset);
         token_client.transfer(&lp, &env.current_contract_address(), &amount);
 
-        let total_shares = get_i128(&env, &DataKey::TotalShares);
-        let total_liquidity = get_i128(&env, &DataKey::TotalLiquidity);
-
         let new_shares = if total_shares == 0 {
             amount
         } else {
@@ -402,11 +748,23 @@ impl LeveragePool {
             panic!("InactiveCollateral");
         }
 
+        let total_deposited = load_collateral_total_deposited(&env, &token);
+        if total_deposited + amount > config.supply_cap {
+            panic!("CollateralCapExceeded");
+        }
+
         let token_client = token::Client::new(&env, &token);
         token_client.transfer(&user, &env.current_contract_address(), &amount);
 
         let new_balance = load_collateral_balance(&env, &user, &token) + amount;
         set_collateral_balance(&env, &user, &token, new_balance);
+        set_collateral_total_deposited(&env, &token, total_deposited + amount);
+
+        // `position.collateral` is the locked-in basket an open position's
+        // health/liquidation math reads; keep it equal to the live balance
+        // for any token already part of that basket so it never drifts from
+        // what `CollateralBalance` (and the physical token transfer) says.
+        sync_position_collateral_leg(&env, &user, &token, new_balance);
 
         env.events().publish(
             (symbol_short!("coll"), symbol_short!("deposit")),
@@ -423,25 +781,26 @@ impl LeveragePool {
             panic!("InsufficientCollateral");
         }
 
-        // If user has open position with this token, check health after withdrawal
+        // If user has an open position backed (in part) by this token, check
+        // aggregate basket health after withdrawal.
         let pos_key = DataKey::TraderPosition(user.clone());
         if let Some(position) = env
             .storage()
             .persistent()
             .get::<_, Position>(&pos_key)
         {
-            if position.collateral_token == token && position.borrowed_amount > 0 {
-                let config = load_collateral_config(&env, &token);
+            if position.borrowed_amount > 0 && position.collateral.get(token.clone()).is_some() {
                 let oracle_address: Address = env
                     .storage()
                     .instance()
                     .get(&DataKey::OracleContract)
                     .unwrap();
-                let price = get_oracle_price(&env, &oracle_address, &config.price_feed_key);
-                let post_health = compute_health(
-                    balance - amount,
-                    price,
-                    config.collateral_factor_bps,
+                let mut projected = position.collateral.clone();
+                projected.set(token.clone(), balance - amount);
+                let post_health = compute_basket_health(
+                    &env,
+                    &oracle_address,
+                    &projected,
                     position.borrowed_amount,
                 );
                 let min_health: u32 = env
@@ -460,7 +819,16 @@ impl LeveragePool {
         let token_client = token::Client::new(&env, &token);
         token_client.transfer(&env.current_contract_address(), &user, &amount);
 
-        set_collateral_balance(&env, &user, &token, balance - amount);
+        let new_balance = balance - amount;
+        set_collateral_balance(&env, &user, &token, new_balance);
+        let total_deposited = load_collateral_total_deposited(&env, &token);
+        set_collateral_total_deposited(&env, &token, total_deposited - amount);
+
+        // See the matching comment in `deposit_collateral`: keep the open
+        // position's frozen basket snapshot equal to the live balance so
+        // `close_position`/`liquidate` never resurrect collateral that has
+        // already physically left the contract.
+        sync_position_collateral_leg(&env, &user, &token, new_balance);
 
         env.events().publish(
             (symbol_short!("coll"), symbol_short!("wdrawn")),
@@ -470,10 +838,14 @@ impl LeveragePool {
 
     // ----- Position functions -----
 
+    /// Opens a position backed by a basket of the caller's already-deposited
+    /// collateral (Solend/Port obligation model): every token listed in
+    /// `collateral_tokens` with a positive deposited balance is locked into
+    /// the position at its full current balance.
     pub fn open_position(
         env: Env,
         user: Address,
-        collateral_token: Address,
+        collateral_tokens: Vec<Address>,
         borrow_amount: i128,
         direction: PositionDirection,
     ) {
@@ -491,26 +863,34 @@ impl LeveragePool {
             panic!("PositionAlreadyOpen");
         }
 
-        let config = load_collateral_config(&env, &collateral_token);
-        if !config.is_active {
-            panic!("InactiveCollateral");
-        }
-
-        let collateral_balance = load_collateral_balance(&env, &user, &collateral_token);
-        if collateral_balance <= 0 {
-            panic!("InsufficientCollateral");
-        }
-
         let oracle_address: Address = env
             .storage()
             .instance()
             .get(&DataKey::OracleContract)
             .unwrap();
-        let price = get_oracle_price(&env, &oracle_address, &config.price_feed_key);
+        let current_ledger = env.ledger().sequence();
+
+        let mut collateral: Map<Address, i128> = Map::new(&env);
+        let mut max_borrowable = 0i128;
+        for token in collateral_tokens.iter() {
+            let config = load_collateral_config(&env, &token);
+            if !config.is_active {
+                panic!("InactiveCollateral");
+            }
+            let balance = load_collateral_balance(&env, &user, &token);
+            if balance <= 0 {
+                continue;
+            }
+            let price = get_oracle_price(&env, &oracle_address, &config.price_feed_key);
+            let factor = effective_collateral_factor(&config, current_ledger);
+            let proceeds = realizable_sale_proceeds(&env, &token, balance, price);
+            max_borrowable += collateral_value(proceeds, factor);
+            collateral.set(token, balance);
+        }
+        if collateral.is_empty() {
+            panic!("InsufficientCollateral");
+        }
 
-        // max_borrowable = collateral * price * factor / (10000 * 10^7)
-        let max_borrowable = collateral_balance * price * (config.collateral_factor_bps as i128)
-            / (10_000 * PRICE_SCALAR);
         if borrow_amount > max_borrowable {
             panic!("BorrowExceedsCollateral");
         }
@@ -522,12 +902,7 @@ impl LeveragePool {
         }
 
         // Health must be above MinHealth * 150% at open
-        let initial_health = compute_health(
-            collateral_balance,
-            price,
-            config.collateral_factor_bps,
-            borrow_amount,
-        );
+        let initial_health = compute_basket_health(&env, &oracle_address, &collateral, borrow_amount);
         let min_health: u32 = env
             .storage()
             .instance()
@@ -538,13 +913,12 @@ impl LeveragePool {
             panic!("InsufficientCollateral");
         }
 
-        let current_ledger = env.ledger().sequence();
+        let current_index = advance_cumulative_index(&env, total_borrowed, total_liquidity);
         let position = Position {
             borrowed_amount: borrow_amount,
-            collateral_token: collateral_token.clone(),
-            collateral_amount: collateral_balance,
+            collateral,
             opened_at_ledger: current_ledger,
-            last_interest_ledger: current_ledger,
+            borrow_index_snapshot: current_index,
             direction: direction.clone(),
         };
 
@@ -556,13 +930,7 @@ impl LeveragePool {
         // client-side by listening to this event. The contract tracks accounting only.
         env.events().publish(
             (symbol_short!("pos"), symbol_short!("opened")),
-            (
-                user,
-                borrow_amount,
-                collateral_token,
-                collateral_balance,
-                initial_health,
-            ),
+            (user, borrow_amount, collateral_tokens, initial_health),
         );
     }
 
@@ -583,24 +951,31 @@ impl LeveragePool {
             .get(&pos_key)
             .unwrap_or_else(|| panic!("NoOpenPosition"));
 
-        let borrow_rate: u32 = env
-            .storage()
-            .instance()
-            .get(&DataKey::BorrowRateBps)
-            .unwrap();
-        accrue_interest_internal(&mut position, borrow_rate, env.ledger().sequence());
+        let total_liquidity = get_i128(&env, &DataKey::TotalLiquidity);
+        let total_borrowed = get_i128(&env, &DataKey::TotalBorrowed);
+        let current_index = advance_cumulative_index(&env, total_borrowed, total_liquidity);
+        let interest = realize_position_debt(&mut position, current_index);
+        if interest != 0 {
+            set_i128(&env, &DataKey::TotalBorrowed, total_borrowed + interest);
+        }
 
-        let config = load_collateral_config(&env, &position.collateral_token);
         let oracle_address: Address = env
             .storage()
             .instance()
             .get(&DataKey::OracleContract)
             .unwrap();
-        let price = get_oracle_price(&env, &oracle_address, &config.price_feed_key);
 
-        // Collateral value in pool asset terms
-        let collateral_value = position.collateral_amount * price / PRICE_SCALAR;
-        let pnl = collateral_value - position.borrowed_amount;
+        // Raw (unweighted) USD value of every deposit in the basket, in pool
+        // asset terms, needed both for pnl and for pro-rata loss absorption.
+        let mut priced: Vec<(Address, i128, i128)> = Vec::new(&env); // (token, amount, price)
+        let mut total_value = 0i128;
+        for (token, amount) in position.collateral.iter() {
+            let config = load_collateral_config(&env, &token);
+            let price = get_oracle_price(&env, &oracle_address, &config.price_feed_key);
+            total_value += amount * price / PRICE_SCALAR;
+            priced.push_back((token, amount, price));
+        }
+        let pnl = total_value - position.borrowed_amount;
 
         let total_borrowed = get_i128(&env, &DataKey::TotalBorrowed);
         set_i128(
@@ -609,7 +984,7 @@ impl LeveragePool {
             total_borrowed - position.borrowed_amount,
         );
 
-        let mut remaining_collateral = position.collateral_amount;
+        let mut remaining_collateral: Map<Address, i128> = position.collateral.clone();
 
         if pnl > 0 {
             // Profitable: pay trader from pool in pool asset
@@ -630,23 +1005,39 @@ impl LeveragePool {
                 );
             }
         } else if pnl < 0 {
-            // Loss: deduct from collateral
+            // Loss: deduct from each deposit pro-rata to its share of the
+            // basket's total value.
             let loss = -pnl;
-            let loss_in_collateral = if price > 0 {
-                loss * PRICE_SCALAR / price
-            } else {
-                0
-            };
-            remaining_collateral = (position.collateral_amount - loss_in_collateral).max(0);
+            remaining_collateral = Map::new(&env);
+            for (token, amount, price) in priced.iter() {
+                let token_value = amount * price / PRICE_SCALAR;
+                let loss_share = if total_value > 0 {
+                    loss * token_value / total_value
+                } else {
+                    0
+                };
+                let loss_in_token = if price > 0 {
+                    loss_share * PRICE_SCALAR / price
+                } else {
+                    0
+                };
+                remaining_collateral.set(token.clone(), (amount - loss_in_token).max(0));
+            }
         }
 
-        // Update collateral balance (user must withdraw separately)
-        set_collateral_balance(
-            &env,
-            &user,
-            &position.collateral_token,
-            remaining_collateral,
-        );
+        // Update collateral balances (user must withdraw separately); any
+        // loss absorbed beyond a deposit's withdrawn balance leaves the
+        // system permanently.
+        for (token, amount) in position.collateral.iter() {
+            let final_amount = remaining_collateral.get(token.clone()).unwrap_or(0);
+            set_collateral_balance(&env, &user, &token, final_amount);
+
+            let forfeited = amount - final_amount;
+            if forfeited > 0 {
+                let total_deposited = load_collateral_total_deposited(&env, &token);
+                set_collateral_total_deposited(&env, &token, total_deposited - forfeited);
+            }
+        }
 
         // Delete position
         env.storage().persistent().remove(&pos_key);
@@ -667,17 +1058,13 @@ impl LeveragePool {
             None => return, // no position, nothing to do
         };
 
-        let borrow_rate: u32 = env
-            .storage()
-            .instance()
-            .get(&DataKey::BorrowRateBps)
-            .unwrap();
-        let interest =
-            accrue_interest_internal(&mut position, borrow_rate, env.ledger().sequence());
+        let total_liquidity = get_i128(&env, &DataKey::TotalLiquidity);
+        let total_borrowed_before = get_i128(&env, &DataKey::TotalBorrowed);
+        let current_index = advance_cumulative_index(&env, total_borrowed_before, total_liquidity);
+        let interest = realize_position_debt(&mut position, current_index);
 
-        if interest > 0 {
-            let total_borrowed = get_i128(&env, &DataKey::TotalBorrowed);
-            set_i128(&env, &DataKey::TotalBorrowed, total_borrowed + interest);
+        if interest != 0 {
+            set_i128(&env, &DataKey::TotalBorrowed, total_borrowed_before + interest);
 
             env.storage().persistent().set(&pos_key, &position);
             extend_persistent(&env, &pos_key);
@@ -685,9 +1072,18 @@ impl LeveragePool {
     }
 
     /// Fully permissionless liquidation. No auth check on liquidator.
-    pub fn liquidate(env: Env, liquidator: Address, user: Address) {
+    ///
+    /// Partial by design: a single call repays at most `LIQUIDATION_CLOSE_FACTOR_BPS`
+    /// of the debt (the caller's `repay_amount` is clamped down to that cap), unless
+    /// the leftover would be uncollectible dust, in which case the cap is lifted to the
+    /// full debt and a call that repays all of it closes the position.
+    pub fn liquidate(env: Env, liquidator: Address, user: Address, repay_amount: i128) {
         extend_instance(&env);
 
+        if repay_amount <= 0 {
+            panic!("InvalidRepayAmount");
+        }
+
         let pos_key = DataKey::TraderPosition(user.clone());
         let mut position: Position = env
             .storage()
@@ -695,25 +1091,24 @@ impl LeveragePool {
             .get(&pos_key)
             .unwrap_or_else(|| panic!("NoOpenPosition"));
 
-        let borrow_rate: u32 = env
-            .storage()
-            .instance()
-            .get(&DataKey::BorrowRateBps)
-            .unwrap();
-        accrue_interest_internal(&mut position, borrow_rate, env.ledger().sequence());
+        let total_liquidity = get_i128(&env, &DataKey::TotalLiquidity);
+        let total_borrowed = get_i128(&env, &DataKey::TotalBorrowed);
+        let current_index = advance_cumulative_index(&env, total_borrowed, total_liquidity);
+        let interest = realize_position_debt(&mut position, current_index);
+        if interest != 0 {
+            set_i128(&env, &DataKey::TotalBorrowed, total_borrowed + interest);
+        }
 
-        let config = load_collateral_config(&env, &position.collateral_token);
         let oracle_address: Address = env
             .storage()
             .instance()
             .get(&DataKey::OracleContract)
             .unwrap();
-        let price = get_oracle_price(&env, &oracle_address, &config.price_feed_key);
 
-        let health = compute_health(
-            position.collateral_amount,
-            price,
-            config.collateral_factor_bps,
+        let health = compute_basket_health(
+            &env,
+            &oracle_address,
+            &position.collateral,
             position.borrowed_amount,
         );
         let min_health: u32 = env
@@ -725,51 +1120,147 @@ impl LeveragePool {
             panic!("PositionHealthy");
         }
 
-        // Liquidator repays the debt in pool asset
+        // A liquidator may close at most LIQUIDATION_CLOSE_FACTOR_BPS (50%) of the
+        // debt per call, unless doing so would leave uncollectible dust behind, in
+        // which case the full remaining debt may be repaid instead.
+        let half_close = position.borrowed_amount * LIQUIDATION_CLOSE_FACTOR_BPS / 10_000;
+        let max_repay = if position.borrowed_amount - half_close < LIQUIDATION_CLOSE_AMOUNT {
+            position.borrowed_amount
+        } else {
+            half_close
+        };
+        let actual_repay = if repay_amount > max_repay {
+            max_repay
+        } else {
+            repay_amount
+        };
+
+        // Liquidator repays (part of) the debt in pool asset
         let pool_asset: Address = env
             .storage()
             .instance()
             .get(&DataKey::PoolAsset)
             .unwrap();
         let pool_token = token::Client::new(&env, &pool_asset);
-        pool_token.transfer(&liquidator, &env.current_contract_address(), &position.borrowed_amount);
+        pool_token.transfer(&liquidator, &env.current_contract_address(), &actual_repay);
 
         // Decrement total borrowed
         let total_borrowed = get_i128(&env, &DataKey::TotalBorrowed);
         set_i128(
             &env,
             &DataKey::TotalBorrowed,
-            total_borrowed - position.borrowed_amount,
+            total_borrowed - actual_repay,
         );
 
-        // Liquidation bonus
+        // Seize a proportional slice of the basket's total value plus the
+        // liquidation bonus, draining the riskiest (lowest collateral-factor)
+        // leg first: price every deposit once, then repeatedly drain
+        // whichever remaining leg currently has the lowest collateral factor.
+        //
+        // Each deposit's "price" here is the DEX-simulated effective price
+        // for selling its full balance (falling back to the oracle mid when
+        // no pool is configured), not the raw oracle mid, so a position
+        // large relative to available liquidity is valued with slippage.
         let bonus_bps: u32 = env
             .storage()
             .instance()
             .get(&DataKey::LiquidationBonusBps)
             .unwrap();
-        let bonus_amount = position.collateral_amount * (bonus_bps as i128) / 10_000;
-
-        // Transfer full collateral to liquidator (includes embedded bonus)
-        let collateral_token = token::Client::new(&env, &position.collateral_token);
-        collateral_token.transfer(
-            &env.current_contract_address(),
-            &liquidator,
-            &position.collateral_amount,
-        );
 
-        // Clean up
-        set_collateral_balance(&env, &user, &position.collateral_token, 0);
-        env.storage().persistent().remove(&pos_key);
+        let mut priced: Vec<(Address, i128, i128, u32)> = Vec::new(&env); // (token, amount, effective_price, collateral_factor_bps)
+        let mut total_collateral_value = 0i128;
+        for (token, amount) in position.collateral.iter() {
+            let config = load_collateral_config(&env, &token);
+            let oracle_price = get_oracle_price(&env, &oracle_address, &config.price_feed_key);
+            let proceeds = realizable_sale_proceeds(&env, &token, amount, oracle_price);
+            let effective_price = if amount > 0 {
+                proceeds * PRICE_SCALAR / amount
+            } else {
+                oracle_price
+            };
+            total_collateral_value += proceeds;
+            priced.push_back((token, amount, effective_price, config.collateral_factor_bps));
+        }
+
+        let proportional_value = total_collateral_value * actual_repay / position.borrowed_amount;
+        let bonus_value = proportional_value * (bonus_bps as i128) / 10_000;
+        let mut seize_value = (proportional_value + bonus_value).min(total_collateral_value);
+
+        let mut new_collateral: Map<Address, i128> = position.collateral.clone();
+        let mut remaining = priced;
+        while seize_value > 0 && remaining.len() > 0 {
+            let mut min_idx = 0u32;
+            let mut min_factor = u32::MAX;
+            for i in 0..remaining.len() {
+                let (_, _, _, factor) = remaining.get(i).unwrap();
+                if factor < min_factor {
+                    min_factor = factor;
+                    min_idx = i;
+                }
+            }
+            let (token, amount, price, _) = remaining.get(min_idx).unwrap();
+            let token_value = amount * price / PRICE_SCALAR;
+            let seize_this_value = seize_value.min(token_value);
+            let seize_this_amount = if price > 0 {
+                (seize_this_value * PRICE_SCALAR / price).min(amount)
+            } else {
+                0
+            };
+
+            if seize_this_amount > 0 {
+                let collateral_client = token::Client::new(&env, &token);
+                collateral_client.transfer(
+                    &env.current_contract_address(),
+                    &liquidator,
+                    &seize_this_amount,
+                );
+
+                let new_amount = amount - seize_this_amount;
+                new_collateral.set(token.clone(), new_amount);
+
+                let total_deposited = load_collateral_total_deposited(&env, &token);
+                set_collateral_total_deposited(&env, &token, total_deposited - seize_this_amount);
+            }
+
+            seize_value -= seize_this_value;
+
+            let mut next = Vec::new(&env);
+            for i in 0..remaining.len() {
+                if i != min_idx {
+                    next.push_back(remaining.get(i).unwrap());
+                }
+            }
+            remaining = next;
+        }
+
+        position.borrowed_amount -= actual_repay;
+
+        if position.borrowed_amount == 0 {
+            for (token, amount) in new_collateral.iter() {
+                if amount > 0 {
+                    let total_deposited = load_collateral_total_deposited(&env, &token);
+                    set_collateral_total_deposited(&env, &token, total_deposited - amount);
+                }
+                set_collateral_balance(&env, &user, &token, 0);
+            }
+            env.storage().persistent().remove(&pos_key);
+        } else {
+            for (token, amount) in new_collateral.iter() {
+                set_collateral_balance(&env, &user, &token, amount);
+            }
+            position.collateral = new_collateral;
+            env.storage().persistent().set(&pos_key, &position);
+            extend_persistent(&env, &pos_key);
+        }
 
         env.events().publish(
-            (symbol_short!("liq"),),
+            (symbol_short!("pos"), symbol_short!("liquid")),
             (
                 user,
                 liquidator,
-                position.borrowed_amount,
-                position.collateral_amount,
-                bonus_amount,
+                actual_repay,
+                proportional_value + bonus_value,
+                bonus_value,
             ),
         );
     }
@@ -785,20 +1276,22 @@ impl LeveragePool {
             None => return i128::MAX,
         };
 
-        let config = load_collateral_config(&env, &position.collateral_token);
         let oracle_address: Address = env
             .storage()
             .instance()
             .get(&DataKey::OracleContract)
             .unwrap();
-        let price = get_oracle_price(&env, &oracle_address, &config.price_feed_key);
+        let debt = current_position_debt(&env, &position);
 
-        compute_health(
-            position.collateral_amount,
-            price,
-            config.collateral_factor_bps,
-            position.borrowed_amount,
-        )
+        compute_basket_health(&env, &oracle_address, &position.collateral, debt)
+    }
+
+    /// Read-only index-adjusted debt for a position, without realizing it.
+    pub fn get_position_debt(env: Env, user: Address) -> Option<i128> {
+        extend_instance(&env);
+        let pos_key = DataKey::TraderPosition(user);
+        let position: Position = env.storage().persistent().get(&pos_key)?;
+        Some(current_position_debt(&env, &position))
     }
 
     pub fn get_position(env: Env, user: Address) -> Option<Position> {
@@ -818,24 +1311,15 @@ impl LeveragePool {
         let total_borrowed = get_i128(&env, &DataKey::TotalBorrowed);
         let total_shares = get_i128(&env, &DataKey::TotalShares);
 
-        let utilization_rate_bps = if total_liquidity > 0 {
-            (total_borrowed * 10_000 / total_liquidity) as u32
-        } else {
-            0u32
-        };
-
-        let current_borrow_rate_bps: u32 = env
-            .storage()
-            .instance()
-            .get(&DataKey::BorrowRateBps)
-            .unwrap_or(0);
+        let utilization_rate_bps = current_utilization_bps(total_borrowed, total_liquidity);
+        let borrow_rate_bps = current_borrow_rate_bps(&env, total_borrowed, total_liquidity);
 
         PoolStats {
             total_liquidity,
             total_borrowed,
             total_shares,
             utilization_rate_bps,
-            current_borrow_rate_bps,
+            current_borrow_rate_bps: borrow_rate_bps,
         }
     }
 
@@ -890,16 +1374,14 @@ mod test {
         }
     }
 
-    // Mock Oracle — returns 0.5 price (5_000_000)
     #[contract]
-    pub struct MockOracleHalf;
+    pub struct MockDexPool;
     #[contractimpl]
-    impl MockOracleHalf {
-        pub fn lastprice(_env: Env, _asset: Symbol) -> Option<PriceData> {
-            Some(PriceData {
-                price: 5_000_000, // 0.5
-                timestamp: 0,
-            })
+    impl MockDexPool {
+        /// Simulates a thin market: selling into it only realizes 60% of
+        /// oracle-parity proceeds, modeling slippage on a large fill.
+        pub fn simulate_sell(_env: Env, amount_in: i128) -> i128 {
+            amount_in * 6 / 10
         }
     }
 
@@ -935,10 +1417,16 @@ mod test {
             &pool_asset,
             &oracle_id,
             &zkauth_id,
-            &500u32,  // 5% borrow rate per 1000 ledgers
+            &8000u32,   // 80% optimal utilization
+            &0u32,      // 0% base rate
+            &1000u32,   // 10% slope below optimal
+            &10000u32,  // 100% slope above optimal
             &500u32,  // 5% liquidation bonus
             &100000u32, // 10x max leverage
             &10000u32,  // 1.0 min health
+            &1_000_000_0000000i128, // pool-wide liquidity cap
+            &3600u32, // max price age: 1 hour
+            &2000u32, // max price deviation: 20%
         );
 
         // Add collateral type
@@ -946,6 +1434,10 @@ mod test {
             collateral_factor_bps: 7500, // 75%
             price_feed_key: Symbol::new(&env, "XLM"),
             is_active: true,
+            supply_cap: 1_000_000_0000000i128,
+            target_collateral_factor_bps: 7500,
+            start_ledger: 0,
+            end_ledger: 0,
         };
         client.set_collateral_type(&admin, &coll_token, &config);
 
@@ -981,7 +1473,8 @@ mod test {
 
         client.initialize(
             &admin, &pool_asset, &oracle_id, &zkauth_id,
-            &500u32, &500u32, &100000u32, &10000u32,
+            &8000u32, &0u32, &1000u32, &10000u32, &500u32, &100000u32, &10000u32, &1_000_000_0000000i128,
+            &3600u32, &2000u32,
         );
 
         let pool_sac = token::StellarAssetClient::new(&env, &pool_asset);
@@ -1024,13 +1517,18 @@ mod test {
 
         client.initialize(
             &admin, &pool_asset, &oracle_id, &zkauth_id,
-            &500u32, &500u32, &100000u32, &10000u32,
+            &8000u32, &0u32, &1000u32, &10000u32, &500u32, &100000u32, &10000u32, &1_000_000_0000000i128,
+            &3600u32, &2000u32,
         );
 
         let config = CollateralConfig {
             collateral_factor_bps: 7500,
             price_feed_key: Symbol::new(&env, "XLM"),
             is_active: true,
+            supply_cap: 1_000_000_0000000i128,
+            target_collateral_factor_bps: 7500,
+            start_ledger: 0,
+            end_ledger: 0,
         };
         client.set_collateral_type(&admin, &coll_token, &config);
 
@@ -1046,7 +1544,7 @@ mod test {
         let user = Address::generate(&env);
         coll_sac.mint(&user, &20_000_0000000i128);
         client.deposit_collateral(&user, &coll_token, &20_000_0000000i128);
-        client.open_position(&user, &coll_token, &9_000_0000000i128, &PositionDirection::Long);
+        client.open_position(&user, &vec![&env, coll_token.clone()], &9_000_0000000i128, &PositionDirection::Long);
 
         // Available = 10000 - 9000 = 1000. LP tries to withdraw all 10000 shares.
         let lp_shares = 10_000_0000000i128; // LP got 1:1 shares
@@ -1069,6 +1567,98 @@ mod test {
         client.withdraw_collateral(&user, &coll_token, &300_0000000i128);
     }
 
+    #[test]
+    fn test_withdraw_while_open_then_close_does_not_resurrect_collateral() {
+        // Regression test: withdrawing part of a basket leg while a position
+        // is open must update `position.collateral`, not just the live
+        // `CollateralBalance` — otherwise close_position's stale snapshot
+        // overwrites the balance back up and hands the user phantom tokens.
+        let (env, client, _admin, _pool_asset, coll_token, _, _) = setup();
+        let user = Address::generate(&env);
+
+        let coll_sac = token::StellarAssetClient::new(&env, &coll_token);
+        coll_sac.mint(&user, &1_000_0000000i128);
+        client.deposit_collateral(&user, &coll_token, &1_000_0000000i128);
+        client.open_position(
+            &user,
+            &vec![&env, coll_token.clone()],
+            &100_0000000i128,
+            &PositionDirection::Long,
+        );
+
+        // Withdraw most of the collateral while the position stays open;
+        // the tokens physically leave the contract.
+        client.withdraw_collateral(&user, &coll_token, &700_0000000i128);
+
+        let coll_client = token::Client::new(&env, &coll_token);
+        assert_eq!(coll_client.balance(&user), 700_0000000i128);
+
+        client.close_position(&user);
+
+        // Only the still-locked 300 should be withdrawable — the 700 already
+        // paid out must not be payable a second time.
+        client.withdraw_collateral(&user, &coll_token, &300_0000000i128);
+        assert_eq!(coll_client.balance(&user), 1_000_0000000i128);
+    }
+
+    #[test]
+    #[should_panic(expected = "CollateralCapExceeded")]
+    fn test_collateral_supply_cap_rejects_excess_deposit() {
+        let (env, client, admin, _pool_asset, coll_token, _, _) = setup();
+
+        let capped_config = CollateralConfig {
+            collateral_factor_bps: 7500,
+            price_feed_key: Symbol::new(&env, "XLM"),
+            is_active: true,
+            supply_cap: 1000_0000000i128,
+            target_collateral_factor_bps: 7500,
+            start_ledger: 0,
+            end_ledger: 0,
+        };
+        client.set_collateral_type(&admin, &coll_token, &capped_config);
+
+        let user = Address::generate(&env);
+        let coll_sac = token::StellarAssetClient::new(&env, &coll_token);
+        coll_sac.mint(&user, &2000_0000000i128);
+
+        client.deposit_collateral(&user, &coll_token, &1000_0000000i128);
+        // Pushes the aggregate over the 1000-unit cap.
+        client.deposit_collateral(&user, &coll_token, &1i128);
+    }
+
+    #[test]
+    #[should_panic(expected = "PoolLiquidityCapExceeded")]
+    fn test_pool_liquidity_cap_rejects_excess_lp_deposit() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let zkauth_id = env.register(MockZKAuth, ());
+        let oracle_id = env.register(MockOracle, ());
+        let pool_admin = Address::generate(&env);
+        let pool_asset_contract = env.register_stellar_asset_contract_v2(pool_admin.clone());
+        let pool_asset = pool_asset_contract.address();
+        let contract_id = env.register(LeveragePool, ());
+        let client = LeveragePoolClient::new(&env, &contract_id);
+        let coll_token = Address::generate(&env);
+
+        client.initialize(
+            &admin, &pool_asset, &oracle_id, &zkauth_id,
+            &8000u32, &0u32, &1000u32, &10000u32, &500u32, &100000u32, &10000u32,
+            &1000_0000000i128, // pool-wide liquidity cap
+            &3600u32, &2000u32,
+        );
+        let _ = coll_token;
+
+        let pool_sac = token::StellarAssetClient::new(&env, &pool_asset);
+        let lp = Address::generate(&env);
+        pool_sac.mint(&lp, &2000_0000000i128);
+
+        client.lp_deposit(&lp, &1000_0000000i128);
+        // Pushes total liquidity over the 1000-unit pool cap.
+        client.lp_deposit(&lp, &1i128);
+    }
+
     #[test]
     fn test_open_position_with_valid_session() {
         let (env, client, _admin, _pool_asset, coll_token, _, _) = setup();
@@ -1084,14 +1674,14 @@ mod test {
         // Borrow 5000 to have good health
         client.open_position(
             &user,
-            &coll_token,
+            &vec![&env, coll_token.clone()],
             &5_000_0000000i128,
             &PositionDirection::Long,
         );
 
         let pos = client.get_position(&user).unwrap();
         assert_eq!(pos.borrowed_amount, 5_000_0000000i128);
-        assert_eq!(pos.collateral_amount, 10_000_0000000i128);
+        assert_eq!(pos.collateral.get(coll_token).unwrap(), 10_000_0000000i128);
 
         let health = client.get_health_ratio(&user);
         assert!(health > 10_000); // > 1.0
@@ -1107,7 +1697,7 @@ mod test {
         client.deposit_collateral(&user, &coll_token, &10_000_0000000i128);
         client.open_position(
             &user,
-            &coll_token,
+            &vec![&env, coll_token.clone()],
             &3_000_0000000i128,
             &PositionDirection::Long,
         );
@@ -1117,14 +1707,130 @@ mod test {
         client.accrue_interest(&user);
 
         let pos = client.get_position(&user).unwrap();
-        // interest = 3000 * 500 * 1000 / (10000 * 1000) = 150
-        let expected_interest = 150_0000000i128;
+        // utilization = 3000/50000 = 600bps, below the 8000bps optimal kink, so
+        // rate = base(0) + slope1(1000) * 600 / 8000 = 75bps
+        // interest = 3_000_0000000 * 75 * 1000 / (10_000 * 1000) = 225_000_000
+        let expected_interest = 225_000_000i128;
         assert_eq!(
             pos.borrowed_amount,
             3_000_0000000i128 + expected_interest
         );
     }
 
+    #[test]
+    fn test_health_ratio_accrues_without_explicit_poke() {
+        // get_position_debt / get_health_ratio must reflect the cumulative
+        // index live, even if nobody has called accrue_interest — no stale
+        // "too healthy" reads just because a position was never poked.
+        let (env, client, _admin, _pool_asset, coll_token, _, _) = setup();
+        let user = Address::generate(&env);
+
+        let coll_sac = token::StellarAssetClient::new(&env, &coll_token);
+        coll_sac.mint(&user, &10_000_0000000i128);
+        client.deposit_collateral(&user, &coll_token, &10_000_0000000i128);
+        client.open_position(
+            &user,
+            &vec![&env, coll_token.clone()],
+            &3_000_0000000i128,
+            &PositionDirection::Long,
+        );
+
+        let debt_before = client.get_position_debt(&user).unwrap();
+        let health_before = client.get_health_ratio(&user);
+        assert_eq!(debt_before, 3_000_0000000i128);
+
+        // Advance a full interest period without ever calling accrue_interest.
+        env.ledger().set_sequence_number(env.ledger().sequence() + 1000);
+
+        let debt_after = client.get_position_debt(&user).unwrap();
+        let health_after = client.get_health_ratio(&user);
+        assert_eq!(debt_after, 3_000_0000000i128 + 225_000_000i128);
+        assert!(health_after < health_before); // more debt, same collateral
+
+        // The stored position itself is untouched until something pokes it.
+        assert_eq!(
+            client.get_position(&user).unwrap().borrowed_amount,
+            3_000_0000000i128
+        );
+    }
+
+    #[test]
+    fn test_compound_interest_across_multiple_periods() {
+        let (env, client, _admin, _pool_asset, coll_token, _, _) = setup();
+        let user = Address::generate(&env);
+
+        let coll_sac = token::StellarAssetClient::new(&env, &coll_token);
+        coll_sac.mint(&user, &10_000_0000000i128);
+        client.deposit_collateral(&user, &coll_token, &10_000_0000000i128);
+        client.open_position(
+            &user,
+            &vec![&env, coll_token.clone()],
+            &3_000_0000000i128,
+            &PositionDirection::Long,
+        );
+
+        // Two successive 1000-ledger periods at the same 75bps rate must
+        // compound (second period's interest is computed on the already-grown
+        // balance), not just double the first period's flat interest.
+        env.ledger().set_sequence_number(env.ledger().sequence() + 1000);
+        client.accrue_interest(&user);
+        let after_first = client.get_position(&user).unwrap().borrowed_amount;
+        assert_eq!(after_first, 3_000_0000000i128 + 225_000_000i128);
+
+        env.ledger().set_sequence_number(env.ledger().sequence() + 1000);
+        client.accrue_interest(&user);
+        let after_second = client.get_position(&user).unwrap().borrowed_amount;
+
+        let second_period_interest = after_second - after_first;
+        assert!(second_period_interest > 225_000_000i128); // compounds on the grown balance
+
+        // get_position_debt reflects the same realized (index-adjusted) debt.
+        assert_eq!(client.get_position_debt(&user), Some(after_second));
+    }
+
+    #[test]
+    fn test_kinked_borrow_rate_curve() {
+        let (env, client, _admin, _pool_asset, coll_token, _, _) = setup();
+
+        // setup() seeds 50_000 of pool liquidity with nothing borrowed yet.
+        let stats = client.get_pool_stats();
+        assert_eq!(stats.utilization_rate_bps, 0);
+        assert_eq!(stats.current_borrow_rate_bps, 0); // base_rate_bps at 0% utilization
+
+        // Borrow exactly up to the 80% optimal kink (40_000 / 50_000):
+        // rate = base(0) + slope1(1000) * 8000 / 8000 = 1000bps
+        let borrower = Address::generate(&env);
+        let coll_sac = token::StellarAssetClient::new(&env, &coll_token);
+        coll_sac.mint(&borrower, &100_000_0000000i128);
+        client.deposit_collateral(&borrower, &coll_token, &100_000_0000000i128);
+        client.open_position(
+            &borrower,
+            &vec![&env, coll_token.clone()],
+            &40_000_0000000i128,
+            &PositionDirection::Long,
+        );
+
+        let stats = client.get_pool_stats();
+        assert_eq!(stats.utilization_rate_bps, 8000);
+        assert_eq!(stats.current_borrow_rate_bps, 1000);
+
+        // Push utilization to 90% (45_000 / 50_000), past the 80% kink:
+        // rate = base(0) + slope1(1000) + slope2(10000) * (9000-8000)/(10000-8000) = 6000bps
+        let borrower2 = Address::generate(&env);
+        coll_sac.mint(&borrower2, &100_000_0000000i128);
+        client.deposit_collateral(&borrower2, &coll_token, &100_000_0000000i128);
+        client.open_position(
+            &borrower2,
+            &vec![&env, coll_token.clone()],
+            &5_000_0000000i128,
+            &PositionDirection::Long,
+        );
+
+        let stats = client.get_pool_stats();
+        assert_eq!(stats.utilization_rate_bps, 9000);
+        assert_eq!(stats.current_borrow_rate_bps, 6000);
+    }
+
     #[test]
     fn test_health_ratio_at_multiple_prices() {
         let (env, client, _admin, _pool_asset, coll_token, _, _) = setup();
@@ -1135,7 +1841,7 @@ mod test {
         client.deposit_collateral(&user, &coll_token, &10_000_0000000i128);
         client.open_position(
             &user,
-            &coll_token,
+            &vec![&env, coll_token.clone()],
             &5_000_0000000i128,
             &PositionDirection::Long,
         );
@@ -1146,6 +1852,56 @@ mod test {
         assert_eq!(health, 15_000);
     }
 
+    #[test]
+    fn test_gradual_collateral_factor_change() {
+        let (env, client, admin, _pool_asset, coll_token, _, _) = setup();
+        let user = Address::generate(&env);
+
+        let coll_sac = token::StellarAssetClient::new(&env, &coll_token);
+        coll_sac.mint(&user, &10_000_0000000i128);
+        client.deposit_collateral(&user, &coll_token, &10_000_0000000i128);
+        client.open_position(
+            &user,
+            &vec![&env, coll_token.clone()],
+            &5_000_0000000i128,
+            &PositionDirection::Long,
+        );
+
+        // health at factor 75% = 15000 (see test_health_ratio_at_multiple_prices).
+        assert_eq!(client.get_health_ratio(&user), 15_000);
+
+        // Schedule a glide from 75% down to 25% over a 1000-ledger window
+        // instead of applying the drop instantly.
+        let start_ledger = env.ledger().sequence();
+        let end_ledger = start_ledger + 1000;
+        let config = CollateralConfig {
+            collateral_factor_bps: 7500,
+            price_feed_key: Symbol::new(&env, "XLM"),
+            is_active: true,
+            supply_cap: 1_000_000_0000000i128,
+            target_collateral_factor_bps: 2500,
+            start_ledger,
+            end_ledger,
+        };
+        client.set_collateral_type(&admin, &coll_token, &config);
+
+        // Before the window opens, the factor (and health) is unchanged.
+        assert_eq!(client.get_health_ratio(&user), 15_000);
+
+        // Halfway through the window the factor has eased to 50%. Debt also
+        // accrues live: utilization 1000bps -> rate 125bps per 1000 ledgers, so
+        // 500 ledgers grow the index by 62bps and debt = 5000 * 1.0062 = 5031:
+        // health = (10000 * 1.0 * 0.50) / 5031 * HEALTH_SCALAR = 9938.
+        env.ledger().set_sequence_number(start_ledger + 500);
+        assert_eq!(client.get_health_ratio(&user), 9_938);
+
+        // Past the window the factor is fully at the target 25%; 1001 ledgers
+        // grow the index by 125bps, so debt = 5000 * 1.0125 = 5062.5:
+        // health = (10000 * 1.0 * 0.25) / 5062.5 * HEALTH_SCALAR = 4938.
+        env.ledger().set_sequence_number(end_ledger + 1);
+        assert_eq!(client.get_health_ratio(&user), 4_938);
+    }
+
     #[test]
     #[should_panic(expected = "AgentSessionInvalid")]
     fn test_agent_call_rejected_invalid_session() {
@@ -1163,13 +1919,14 @@ mod test {
 
         client.initialize(
             &admin, &pool_asset, &oracle_id, &zkauth_id,
-            &500u32, &500u32, &100000u32, &10000u32,
+            &8000u32, &0u32, &1000u32, &10000u32, &500u32, &100000u32, &10000u32, &1_000_000_0000000i128,
+            &3600u32, &2000u32,
         );
 
         let user = Address::generate(&env);
         client.open_position(
             &user,
-            &Address::generate(&env),
+            &vec![&env, Address::generate(&env)],
             &100i128,
             &PositionDirection::Long,
         );
@@ -1187,7 +1944,7 @@ mod test {
         // Borrow 5000 USDC against 10000 XLM at price 1.0
         client.open_position(
             &user,
-            &coll_token,
+            &vec![&env, coll_token.clone()],
             &5_000_0000000i128,
             &PositionDirection::Long,
         );
@@ -1201,60 +1958,187 @@ mod test {
 
     #[test]
     fn test_liquidation() {
-        // Use the half-price oracle so positions become unhealthy
-        let env = Env::default();
-        env.mock_all_auths();
+        let (env, client, admin, pool_asset, coll_token, _, _) = setup();
 
-        let admin = Address::generate(&env);
-        let zkauth_id = env.register(MockZKAuth, ());
-        let oracle_normal = env.register(MockOracle, ());
-        let oracle_half = env.register(MockOracleHalf, ());
+        let user = Address::generate(&env);
+        let coll_sac = token::StellarAssetClient::new(&env, &coll_token);
+        coll_sac.mint(&user, &10_000_0000000i128);
+        client.deposit_collateral(&user, &coll_token, &10_000_0000000i128);
+        client.open_position(
+            &user,
+            &vec![&env, coll_token.clone()],
+            &5_000_0000000i128,
+            &PositionDirection::Long,
+        );
 
-        let pool_admin = Address::generate(&env);
-        let coll_admin = Address::generate(&env);
-        let pool_asset_contract = env.register_stellar_asset_contract_v2(pool_admin.clone());
-        let coll_token_contract = env.register_stellar_asset_contract_v2(coll_admin.clone());
-        let pool_asset = pool_asset_contract.address();
-        let coll_token = coll_token_contract.address();
+        // Drop the collateral factor to push the position underwater
+        // (health = 10000 * 1.0 * 3000/10000 * 10000 / 5000 = 6000 < min_health 10000).
+        let underwater_config = CollateralConfig {
+            collateral_factor_bps: 3000,
+            price_feed_key: Symbol::new(&env, "XLM"),
+            is_active: true,
+            supply_cap: 1_000_000_0000000i128,
+            target_collateral_factor_bps: 3000,
+            start_ledger: 0,
+            end_ledger: 0,
+        };
+        client.set_collateral_type(&admin, &coll_token, &underwater_config);
+        assert!(client.get_health_ratio(&user) < 10_000);
 
-        // Initialize with normal oracle first
-        let contract_id = env.register(LeveragePool, ());
-        let client = LeveragePoolClient::new(&env, &contract_id);
+        // A liquidator may only close 50% of the debt per call.
+        let liquidator = Address::generate(&env);
+        let pool_sac = token::StellarAssetClient::new(&env, &pool_asset);
+        pool_sac.mint(&liquidator, &10_000_0000000i128);
+        client.liquidate(&liquidator, &user, &3_000_0000000i128);
 
-        client.initialize(
-            &admin, &pool_asset, &oracle_normal, &zkauth_id,
-            &500u32, &500u32, &100000u32, &10000u32,
-        );
+        let pos = client.get_position(&user).unwrap();
+        assert_eq!(pos.borrowed_amount, 2_500_0000000i128); // 5000 - 2500 (50% cap)
+        assert_eq!(
+            pos.collateral.get(coll_token.clone()).unwrap(),
+            4_750_0000000i128
+        ); // 10000 - (5000 + 250 bonus)
 
-        let config = CollateralConfig {
-            collateral_factor_bps: 7500,
+        let coll_client = token::Client::new(&env, &coll_token);
+        assert_eq!(coll_client.balance(&liquidator), 5_250_0000000i128);
+
+        assert_eq!(client.get_pool_stats().total_borrowed, 2_500_0000000i128);
+    }
+
+    #[test]
+    fn test_liquidation_dust_allows_full_close() {
+        let (env, client, admin, pool_asset, coll_token, _, _) = setup();
+
+        let user = Address::generate(&env);
+        let coll_sac = token::StellarAssetClient::new(&env, &coll_token);
+        coll_sac.mint(&user, &100i128);
+        client.deposit_collateral(&user, &coll_token, &100i128);
+        client.open_position(&user, &vec![&env, coll_token.clone()], &2i128, &PositionDirection::Long);
+
+        // Crush the collateral factor so the tiny position is unhealthy.
+        let underwater_config = CollateralConfig {
+            collateral_factor_bps: 1,
             price_feed_key: Symbol::new(&env, "XLM"),
             is_active: true,
+            supply_cap: 1_000_000_0000000i128,
+            target_collateral_factor_bps: 1,
+            start_ledger: 0,
+            end_ledger: 0,
         };
-        client.set_collateral_type(&admin, &coll_token, &config);
+        client.set_collateral_type(&admin, &coll_token, &underwater_config);
+        assert!(client.get_health_ratio(&user) < 10_000);
 
-        // Fund pool
+        // Half of the 2-unit debt (1 unit) would leave 1 unit of dust behind
+        // (below LIQUIDATION_CLOSE_AMOUNT), so the full debt may be repaid instead.
+        let liquidator = Address::generate(&env);
         let pool_sac = token::StellarAssetClient::new(&env, &pool_asset);
+        pool_sac.mint(&liquidator, &1_000i128);
+        client.liquidate(&liquidator, &user, &1_000i128);
+
+        assert!(client.get_position(&user).is_none());
+    }
+
+    #[test]
+    fn test_liquidation_seizes_lowest_collateral_factor_leg_first() {
+        let (env, client, admin, pool_asset, coll_token, _, _) = setup();
+
+        // A second, riskier collateral type with a lower collateral factor.
+        let token2_admin = Address::generate(&env);
+        let token2_contract = env.register_stellar_asset_contract_v2(token2_admin);
+        let token2 = token2_contract.address();
+        let token2_config = CollateralConfig {
+            collateral_factor_bps: 2000,
+            price_feed_key: Symbol::new(&env, "TOK2"),
+            is_active: true,
+            supply_cap: 1_000_000_0000000i128,
+            target_collateral_factor_bps: 2000,
+            start_ledger: 0,
+            end_ledger: 0,
+        };
+        client.set_collateral_type(&admin, &token2, &token2_config);
+
+        let user = Address::generate(&env);
         let coll_sac = token::StellarAssetClient::new(&env, &coll_token);
-        let lp = Address::generate(&env);
-        pool_sac.mint(&lp, &100_000_0000000i128);
-        client.lp_deposit(&lp, &50_000_0000000i128);
+        coll_sac.mint(&user, &5_000_0000000i128);
+        client.deposit_collateral(&user, &coll_token, &5_000_0000000i128);
+        let token2_sac = token::StellarAssetClient::new(&env, &token2);
+        token2_sac.mint(&user, &5_000_0000000i128);
+        client.deposit_collateral(&user, &token2, &5_000_0000000i128);
+
+        client.open_position(
+            &user,
+            &vec![&env, coll_token.clone(), token2.clone()],
+            &3_000_0000000i128,
+            &PositionDirection::Long,
+        );
+
+        // Crush coll_token's factor, but leave it above token2's — token2
+        // remains the riskiest (lowest factor) leg in the basket.
+        let underwater_config = CollateralConfig {
+            collateral_factor_bps: 3000,
+            price_feed_key: Symbol::new(&env, "XLM"),
+            is_active: true,
+            supply_cap: 1_000_000_0000000i128,
+            target_collateral_factor_bps: 3000,
+            start_ledger: 0,
+            end_ledger: 0,
+        };
+        client.set_collateral_type(&admin, &coll_token, &underwater_config);
+        assert!(client.get_health_ratio(&user) < 10_000);
+
+        let liquidator = Address::generate(&env);
+        let pool_sac = token::StellarAssetClient::new(&env, &pool_asset);
+        pool_sac.mint(&liquidator, &10_000_0000000i128);
+        client.liquidate(&liquidator, &user, &2_000_0000000i128);
+
+        // token2 (factor 2000, the riskiest leg) is drained first and fully;
+        // coll_token (factor 3000) is only touched for the small remainder.
+        let pos = client.get_position(&user).unwrap();
+        assert_eq!(pos.collateral.get(token2.clone()).unwrap(), 0i128);
+        assert_eq!(
+            pos.collateral.get(coll_token.clone()).unwrap(),
+            4_750_0000000i128
+        );
+
+        let token2_client = token::Client::new(&env, &token2);
+        assert_eq!(token2_client.balance(&liquidator), 5_000_0000000i128);
+        let coll_client = token::Client::new(&env, &coll_token);
+        assert_eq!(coll_client.balance(&liquidator), 250_0000000i128);
+    }
+
+    #[test]
+    fn test_dex_slippage_makes_thin_collateral_liquidatable() {
+        // A position that looks healthy under the raw oracle mid (health
+        // 15000bps, above the 10000bps minimum) should become liquidatable
+        // once its collateral is only worth 60% of oracle parity to actually
+        // sell, per the configured DEX pool's simulated depth.
+        let (env, client, admin, pool_asset, coll_token, _, _) = setup();
 
-        // Open position
         let user = Address::generate(&env);
+        let coll_sac = token::StellarAssetClient::new(&env, &coll_token);
         coll_sac.mint(&user, &10_000_0000000i128);
         client.deposit_collateral(&user, &coll_token, &10_000_0000000i128);
         client.open_position(
             &user,
-            &coll_token,
+            &vec![&env, coll_token.clone()],
             &5_000_0000000i128,
             &PositionDirection::Long,
         );
+        assert!(client.get_health_ratio(&user) >= 10_000); // healthy under the raw oracle mid
+
+        let dex_pool_id = env.register(MockDexPool, ());
+        client.set_dex_pool(&admin, &coll_token, &dex_pool_id);
+        assert!(client.get_health_ratio(&user) < 10_000); // unhealthy once slippage is priced in
+
+        let liquidator = Address::generate(&env);
+        let pool_sac = token::StellarAssetClient::new(&env, &pool_asset);
+        pool_sac.mint(&liquidator, &10_000_0000000i128);
+        client.liquidate(&liquidator, &user, &2_500_0000000i128);
+
+        let pos = client.get_position(&user).unwrap();
+        assert_eq!(pos.borrowed_amount, 2_500_0000000i128); // 5000 - 2500 (50% cap)
 
-        // Now switch to half-price oracle to make position unhealthy
-        // We can't easily switch the oracle, so this test demonstrates the structure.
-        // In a real integration test, the oracle price would drop.
-        // For unit test purposes, the health check logic is verified in test_health_ratio.
+        let coll_client = token::Client::new(&env, &coll_token);
+        assert_eq!(coll_client.balance(&liquidator), 5_250_0000000i128);
     }
 
     #[test]
@@ -1263,7 +2147,8 @@ mod test {
         let (env, client, admin, pool_asset, _, zkauth_id, oracle_id) = setup();
         client.initialize(
             &admin, &pool_asset, &oracle_id, &zkauth_id,
-            &500u32, &500u32, &100000u32, &10000u32,
+            &8000u32, &0u32, &1000u32, &10000u32, &500u32, &100000u32, &10000u32, &1_000_000_0000000i128,
+            &3600u32, &2000u32,
         );
     }
 }
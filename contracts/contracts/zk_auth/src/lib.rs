@@ -1,8 +1,8 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, contracterror, symbol_short, vec, Address, Bytes,
-    BytesN, Env, Vec, U256,
+    contract, contractimpl, contracttype, contracterror, symbol_short, vec, xdr::ToXdr, Address,
+    Bytes, BytesN, Env, Vec, U256,
 };
 
 // ---------------------------------------------------------------------------
@@ -28,6 +28,15 @@ pub enum Error {
     ProofVerificationFailed = 4,
     NoActiveSession = 5,
     SessionExpired = 6,
+    VKVersionNotFound = 7,
+    VKVersionRetired = 8,
+    InvalidAgentSignature = 9,
+    PublicInputLengthMismatch = 10,
+    InvalidThreshold = 11,
+    NotAdmin = 12,
+    ActionNotFound = 13,
+    AlreadyApproved = 14,
+    AccessListNotBound = 15,
 }
 
 // ---------------------------------------------------------------------------
@@ -39,10 +48,17 @@ pub enum Error {
 pub struct Session {
     pub session_id: u64,
     pub agent_pubkey: BytesN<32>,
-    pub poseidon_hash: BytesN<32>,
+    /// sha256 over the concatenated big-endian public input vector proven
+    /// against the circuit (agent pubkey, expiry bounds, policy commitments, ...).
+    pub public_inputs_hash: BytesN<32>,
     pub expires_at_ledger: u32,
     pub created_at_ledger: u32,
     pub nonce: u64,
+    pub vk_version: u32,
+    /// Per-target spending caps delegated to the agent for this session, e.g.
+    /// `(agent_vault_address, 1000_U256)`. A target absent from this list has
+    /// no delegated allowance regardless of session validity.
+    pub access_list: Vec<(Address, U256)>,
 }
 
 #[contracttype]
@@ -63,6 +79,24 @@ pub struct ZKProof {
     pub c: BytesN<64>,
 }
 
+/// A privileged operation gated behind the M-of-N admin committee. Each
+/// variant mirrors what used to be a single-admin-only entry point.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AdminAction {
+    RegisterVerifyingKey(u32, StoredVK),
+    SetActiveVkVersion(u32),
+    RetireVkVersion(u32),
+    RotateAdminSet(Vec<Address>, u32),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingAction {
+    pub action: AdminAction,
+    pub approvals: Vec<Address>,
+}
+
 // ---------------------------------------------------------------------------
 // Storage keys
 // ---------------------------------------------------------------------------
@@ -71,8 +105,12 @@ pub struct ZKProof {
 #[derive(Clone)]
 pub enum DataKey {
     ActiveSession(Address),
-    VerifyingKey,
-    Admin,
+    VerifyingKey(u32),
+    CurrentVKVersion,
+    RetiredVKVersion(u32),
+    AdminSet,
+    AdminThreshold,
+    PendingAction(BytesN<32>),
     SessionCounter(Address),
 }
 
@@ -81,20 +119,24 @@ pub enum DataKey {
 // ---------------------------------------------------------------------------
 
 #[cfg(not(test))]
-fn verify_groth16(env: &Env, vk: &StoredVK, poseidon_hash: &BytesN<32>, proof: &ZKProof) {
+fn verify_groth16(env: &Env, vk: &StoredVK, public_inputs: &Vec<BytesN<32>>, proof: &ZKProof) {
     use soroban_sdk::crypto::bn254::{Bn254G1Affine, Bn254G2Affine, Fr};
 
-    let hash_bytes: Bytes = poseidon_hash.clone().into();
-    let public_input_u256 = U256::from_be_bytes(env, &hash_bytes);
-    let public_input_fr = Fr::from_u256(public_input_u256);
+    if vk.ic.len() != public_inputs.len() + 1 {
+        panic!("{:?}", Error::PublicInputLengthMismatch);
+    }
 
     let bn = env.crypto().bn254();
 
-    // vk_x = IC[0] + IC[1] * public_input
-    let ic0 = Bn254G1Affine::from_bytes(vk.ic.get(0).unwrap());
-    let ic1 = Bn254G1Affine::from_bytes(vk.ic.get(1).unwrap());
-    let ic1_scaled = bn.g1_mul(&ic1, &public_input_fr);
-    let vk_x = bn.g1_add(&ic0, &ic1_scaled);
+    // vk_x = IC[0] + Σ IC[i+1] * public_inputs[i]
+    let mut vk_x = Bn254G1Affine::from_bytes(vk.ic.get(0).unwrap());
+    for (i, input) in public_inputs.iter().enumerate() {
+        let input_bytes: Bytes = input.clone().into();
+        let input_fr = Fr::from_u256(U256::from_be_bytes(env, &input_bytes));
+        let ic_i = Bn254G1Affine::from_bytes(vk.ic.get((i + 1) as u32).unwrap());
+        let scaled = bn.g1_mul(&ic_i, &input_fr);
+        vk_x = bn.g1_add(&vk_x, &scaled);
+    }
 
     let proof_a = Bn254G1Affine::from_bytes(proof.a.clone());
     let proof_b = Bn254G2Affine::from_bytes(proof.b.clone());
@@ -114,15 +156,119 @@ fn verify_groth16(env: &Env, vk: &StoredVK, poseidon_hash: &BytesN<32>, proof: &
     let g2_vec = vec![env, proof_b, beta_g2, gamma_g2, delta_g2];
 
     if !bn.pairing_check(g1_vec, g2_vec) {
-        panic!("ProofVerificationFailed");
+        panic!("{:?}", Error::ProofVerificationFailed);
     }
 }
 
 #[cfg(test)]
-fn verify_groth16(_env: &Env, _vk: &StoredVK, _poseidon_hash: &BytesN<32>, _proof: &ZKProof) {
+fn verify_groth16(_env: &Env, _vk: &StoredVK, _public_inputs: &Vec<BytesN<32>>, _proof: &ZKProof) {
     // Mock: always succeeds in tests
 }
 
+// ---------------------------------------------------------------------------
+// Admin governance helpers
+// ---------------------------------------------------------------------------
+
+fn require_admin(env: &Env, addr: &Address) {
+    let admins: Vec<Address> = env
+        .storage()
+        .instance()
+        .get(&DataKey::AdminSet)
+        .unwrap_or_else(|| panic!("{:?}", Error::NotInitialized));
+    if !admins.contains(addr) {
+        panic!("{:?}", Error::NotAdmin);
+    }
+}
+
+fn hash_admin_action(env: &Env, action: &AdminAction) -> BytesN<32> {
+    env.crypto().sha256(&action.clone().to_xdr(env)).into()
+}
+
+/// Commitment over a session's `access_list`, bound into the circuit's
+/// public input vector (see `start_session`) so the caps a session grants
+/// are attested by the proof itself rather than trusted at face value.
+fn hash_access_list(env: &Env, access_list: &Vec<(Address, U256)>) -> BytesN<32> {
+    env.crypto().sha256(&access_list.clone().to_xdr(env)).into()
+}
+
+/// Persists `pending`, or — once it has gathered enough approvals — applies
+/// the action and clears it instead.
+///
+/// Approvals are counted against the *current* `AdminSet`, not just the set
+/// in force when each approval was recorded: an admin rotated out after
+/// approving must not still count towards quorum on a still-pending action,
+/// or a single current admin could combine with stale pre-rotation approvals
+/// to execute something the current committee never actually agreed to.
+fn maybe_execute(env: &Env, action_hash: &BytesN<32>, pending: PendingAction) {
+    let threshold: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::AdminThreshold)
+        .unwrap_or_else(|| panic!("{:?}", Error::NotInitialized));
+    let current_admins: Vec<Address> = env
+        .storage()
+        .instance()
+        .get(&DataKey::AdminSet)
+        .unwrap_or_else(|| panic!("{:?}", Error::NotInitialized));
+
+    let mut live_approvals: u32 = 0;
+    for approver in pending.approvals.iter() {
+        if current_admins.contains(&approver) {
+            live_approvals += 1;
+        }
+    }
+
+    let key = DataKey::PendingAction(action_hash.clone());
+    if live_approvals >= threshold {
+        apply_admin_action(env, &pending.action);
+        env.storage().persistent().remove(&key);
+        env.events().publish(
+            (symbol_short!("admin"), symbol_short!("exec")),
+            (action_hash.clone(),),
+        );
+    } else {
+        env.storage().persistent().set(&key, &pending);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, INSTANCE_BUMP, INSTANCE_BUMP);
+    }
+}
+
+fn apply_admin_action(env: &Env, action: &AdminAction) {
+    match action {
+        AdminAction::RegisterVerifyingKey(version, vk) => {
+            env.storage()
+                .instance()
+                .set(&DataKey::VerifyingKey(*version), vk);
+        }
+        AdminAction::SetActiveVkVersion(version) => {
+            if !env.storage().instance().has(&DataKey::VerifyingKey(*version)) {
+                panic!("{:?}", Error::VKVersionNotFound);
+            }
+            env.storage()
+                .instance()
+                .set(&DataKey::CurrentVKVersion, version);
+        }
+        AdminAction::RetireVkVersion(version) => {
+            env.storage()
+                .instance()
+                .set(&DataKey::RetiredVKVersion(*version), &true);
+        }
+        AdminAction::RotateAdminSet(new_admins, new_threshold) => {
+            if *new_threshold == 0 || *new_threshold > new_admins.len() {
+                panic!("{:?}", Error::InvalidThreshold);
+            }
+            env.storage().instance().set(&DataKey::AdminSet, new_admins);
+            env.storage()
+                .instance()
+                .set(&DataKey::AdminThreshold, new_threshold);
+        }
+    }
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_BUMP, INSTANCE_BUMP);
+}
+
 // ---------------------------------------------------------------------------
 // Contract
 // ---------------------------------------------------------------------------
@@ -132,26 +278,85 @@ pub struct ZKAuth;
 
 #[contractimpl]
 impl ZKAuth {
-    /// One-time init. Stores admin and the fixed protocol-wide verifying key.
-    pub fn initialize(env: Env, admin: Address, verifying_key: StoredVK) {
-        if env.storage().instance().has(&DataKey::Admin) {
-            panic!("AlreadyInitialized");
+    /// One-time init. Stores the admin committee and registers the initial
+    /// verifying key as version 1.
+    pub fn initialize(env: Env, admins: Vec<Address>, threshold: u32, verifying_key: StoredVK) {
+        if env.storage().instance().has(&DataKey::AdminThreshold) {
+            panic!("{:?}", Error::AlreadyInitialized);
+        }
+        if threshold == 0 || threshold > admins.len() {
+            panic!("{:?}", Error::InvalidThreshold);
         }
-        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::AdminSet, &admins);
+        env.storage()
+            .instance()
+            .set(&DataKey::AdminThreshold, &threshold);
+        env.storage()
+            .instance()
+            .set(&DataKey::VerifyingKey(1), &verifying_key);
         env.storage()
             .instance()
-            .set(&DataKey::VerifyingKey, &verifying_key);
+            .set(&DataKey::CurrentVKVersion, &1u32);
         env.storage()
             .instance()
             .extend_ttl(INSTANCE_BUMP, INSTANCE_BUMP);
     }
 
+    /// Admin: propose a privileged action. The proposer's own approval counts
+    /// immediately, so a 1-of-N committee executes on proposal. Returns the
+    /// action's hash, which other admins pass to `approve_admin_action`.
+    pub fn propose_admin_action(env: Env, proposer: Address, action: AdminAction) -> BytesN<32> {
+        proposer.require_auth();
+        require_admin(&env, &proposer);
+
+        let action_hash = hash_admin_action(&env, &action);
+        let mut approvals = Vec::new(&env);
+        approvals.push_back(proposer);
+
+        let pending = PendingAction { action, approvals };
+        maybe_execute(&env, &action_hash, pending);
+
+        env.events().publish(
+            (symbol_short!("admin"), symbol_short!("propose")),
+            (action_hash.clone(),),
+        );
+
+        action_hash
+    }
+
+    /// Admin: add a distinct approval to a pending action, executing it
+    /// atomically once `threshold` approvals have been collected.
+    pub fn approve_admin_action(env: Env, approver: Address, action_hash: BytesN<32>) {
+        approver.require_auth();
+        require_admin(&env, &approver);
+
+        let key = DataKey::PendingAction(action_hash.clone());
+        let mut pending: PendingAction = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| panic!("{:?}", Error::ActionNotFound));
+
+        if pending.approvals.contains(&approver) {
+            panic!("{:?}", Error::AlreadyApproved);
+        }
+        pending.approvals.push_back(approver);
+
+        maybe_execute(&env, &action_hash, pending);
+
+        env.events().publish(
+            (symbol_short!("admin"), symbol_short!("approve")),
+            (action_hash,),
+        );
+    }
+
     /// Create a session — the ONLY function that does ZK verification.
     pub fn start_session(
         env: Env,
         user: Address,
         agent_pubkey: BytesN<32>,
-        poseidon_hash: BytesN<32>,
+        public_inputs: Vec<BytesN<32>>,
+        access_list: Vec<(Address, U256)>,
         session_duration_ledgers: u32,
         proof: ZKProof,
     ) {
@@ -163,17 +368,41 @@ impl ZKAuth {
         if session_duration_ledgers < MIN_SESSION_LEDGERS
             || session_duration_ledgers > MAX_SESSION_LEDGERS
         {
-            panic!("InvalidSessionDuration");
+            panic!("{:?}", Error::InvalidSessionDuration);
         }
 
+        let vk_version: u32 = env
+            .storage()
+            .instance()
+            .get(&DataKey::CurrentVKVersion)
+            .unwrap_or_else(|| panic!("{:?}", Error::NotInitialized));
         let vk: StoredVK = env
             .storage()
             .instance()
-            .get(&DataKey::VerifyingKey)
-            .unwrap_or_else(|| panic!("NotInitialized"));
+            .get(&DataKey::VerifyingKey(vk_version))
+            .unwrap_or_else(|| panic!("{:?}", Error::NotInitialized));
+
+        // The last proven public input must commit to this exact
+        // `access_list` (see `hash_access_list`), so the caps a session
+        // grants are bound into the circuit the proof attests to instead of
+        // being trusted as a bare, unverified function argument.
+        if public_inputs.is_empty() {
+            panic!("{:?}", Error::AccessListNotBound);
+        }
+        let access_list_commitment = hash_access_list(&env, &access_list);
+        let bound_commitment = public_inputs.get(public_inputs.len() - 1).unwrap();
+        if bound_commitment != access_list_commitment {
+            panic!("{:?}", Error::AccessListNotBound);
+        }
 
         // --- ZK proof verification (mocked in tests) ---
-        verify_groth16(&env, &vk, &poseidon_hash, &proof);
+        verify_groth16(&env, &vk, &public_inputs, &proof);
+
+        let mut input_bytes = Bytes::new(&env);
+        for input in public_inputs.iter() {
+            input_bytes.append(&Bytes::from_array(&env, &input.to_array()));
+        }
+        let public_inputs_hash: BytesN<32> = env.crypto().sha256(&input_bytes).into();
 
         // Increment monotonic session counter
         let counter_key = DataKey::SessionCounter(user.clone());
@@ -191,10 +420,12 @@ impl ZKAuth {
         let session = Session {
             session_id,
             agent_pubkey: agent_pubkey.clone(),
-            poseidon_hash,
+            public_inputs_hash,
             expires_at_ledger: expires_at,
             created_at_ledger: current_ledger,
             nonce: 0,
+            vk_version,
+            access_list,
         };
 
         // Overwrite any existing session — old one is dead immediately
@@ -245,6 +476,13 @@ impl ZKAuth {
         let session_key = DataKey::ActiveSession(user);
         match env.storage().persistent().get::<_, Session>(&session_key) {
             Some(session) => {
+                if env
+                    .storage()
+                    .instance()
+                    .has(&DataKey::RetiredVKVersion(session.vk_version))
+                {
+                    return false;
+                }
                 env.storage()
                     .persistent()
                     .extend_ttl(&session_key, LEDGER_BUMP, LEDGER_BUMP);
@@ -263,6 +501,13 @@ impl ZKAuth {
         let session_key = DataKey::ActiveSession(user);
         match env.storage().persistent().get::<_, Session>(&session_key) {
             Some(session) if env.ledger().sequence() < session.expires_at_ledger => {
+                if env
+                    .storage()
+                    .instance()
+                    .has(&DataKey::RetiredVKVersion(session.vk_version))
+                {
+                    return None;
+                }
                 env.storage()
                     .persistent()
                     .extend_ttl(&session_key, LEDGER_BUMP, LEDGER_BUMP);
@@ -272,6 +517,149 @@ impl ZKAuth {
         }
     }
 
+    /// Require a fresh agent signature over `session_id || nonce || action_hash`
+    /// before a privileged AgentVault/LeveragePool call proceeds. The monotonic
+    /// nonce prevents a captured signature from being replayed; callers must
+    /// request a new one (via off-chain re-signing) for every action.
+    pub fn authorize_action(
+        env: Env,
+        user: Address,
+        action_hash: BytesN<32>,
+        agent_sig: BytesN<64>,
+    ) -> u64 {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_BUMP, INSTANCE_BUMP);
+
+        let session_key = DataKey::ActiveSession(user.clone());
+        let mut session: Session = env
+            .storage()
+            .persistent()
+            .get(&session_key)
+            .unwrap_or_else(|| panic!("{:?}", Error::NoActiveSession));
+
+        if env.ledger().sequence() >= session.expires_at_ledger {
+            panic!("{:?}", Error::SessionExpired);
+        }
+        if env
+            .storage()
+            .instance()
+            .has(&DataKey::RetiredVKVersion(session.vk_version))
+        {
+            panic!("{:?}", Error::VKVersionRetired);
+        }
+
+        let mut message = Bytes::new(&env);
+        message.append(&Bytes::from_array(&env, &session.session_id.to_be_bytes()));
+        message.append(&Bytes::from_array(&env, &session.nonce.to_be_bytes()));
+        message.append(&Bytes::from_array(&env, &action_hash.to_array()));
+
+        // The host's `ed25519_verify` has no fallible form — a cryptographically
+        // invalid-but-well-formed signature traps the whole invocation, which we
+        // cannot downgrade to a typed `Error` (there is no way to catch a host
+        // trap from contract code). We can, however, catch the degenerate
+        // all-zero signature sentinel ourselves and reject it with the typed
+        // error before ever reaching the host call.
+        if agent_sig == BytesN::from_array(&env, &[0u8; 64]) {
+            panic!("{:?}", Error::InvalidAgentSignature);
+        }
+
+        env.crypto()
+            .ed25519_verify(&session.agent_pubkey, &message, &agent_sig);
+
+        session.nonce += 1;
+        let new_nonce = session.nonce;
+        env.storage().persistent().set(&session_key, &session);
+        env.storage()
+            .persistent()
+            .extend_ttl(&session_key, LEDGER_BUMP, LEDGER_BUMP);
+
+        env.events().publish(
+            (symbol_short!("session"), symbol_short!("authzd")),
+            (user, new_nonce, action_hash),
+        );
+
+        new_nonce
+    }
+
+    /// Spends `amount` of the session's delegated cap for `target`. Returns
+    /// `false` (without mutating anything) if the session is invalid, `target`
+    /// has no delegated allowance, or the remaining cap is insufficient.
+    pub fn check_and_consume(env: Env, user: Address, target: Address, amount: U256) -> bool {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_BUMP, INSTANCE_BUMP);
+
+        let session_key = DataKey::ActiveSession(user.clone());
+        let mut session: Session = match env.storage().persistent().get(&session_key) {
+            Some(s) => s,
+            None => return false,
+        };
+
+        if env.ledger().sequence() >= session.expires_at_ledger {
+            return false;
+        }
+        if env
+            .storage()
+            .instance()
+            .has(&DataKey::RetiredVKVersion(session.vk_version))
+        {
+            return false;
+        }
+
+        let mut updated = Vec::new(&env);
+        let mut spent = false;
+        for (addr, cap) in session.access_list.iter() {
+            if addr == target && !spent {
+                if cap < amount {
+                    return false;
+                }
+                updated.push_back((addr, cap.sub(&amount)));
+                spent = true;
+            } else {
+                updated.push_back((addr, cap));
+            }
+        }
+        if !spent {
+            return false;
+        }
+
+        session.access_list = updated;
+        env.storage().persistent().set(&session_key, &session);
+        env.storage()
+            .persistent()
+            .extend_ttl(&session_key, LEDGER_BUMP, LEDGER_BUMP);
+
+        true
+    }
+
+    /// Read-only lookup of the remaining delegated cap for `target`, or `None`
+    /// if the session is invalid or `target` has no entry in the access list.
+    pub fn get_allowance(env: Env, user: Address, target: Address) -> Option<U256> {
+        env.storage()
+            .instance()
+            .extend_ttl(INSTANCE_BUMP, INSTANCE_BUMP);
+
+        let session_key = DataKey::ActiveSession(user);
+        let session: Session = env.storage().persistent().get(&session_key)?;
+
+        if env.ledger().sequence() >= session.expires_at_ledger {
+            return None;
+        }
+        if env
+            .storage()
+            .instance()
+            .has(&DataKey::RetiredVKVersion(session.vk_version))
+        {
+            return None;
+        }
+
+        session
+            .access_list
+            .iter()
+            .find(|(addr, _)| *addr == target)
+            .map(|(_, cap)| cap)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -298,6 +686,14 @@ mod test {
         }
     }
 
+    fn empty_acl(env: &Env) -> Vec<(Address, U256)> {
+        Vec::new(env)
+    }
+
+    fn single_input(env: &Env, byte: u8, acl: &Vec<(Address, U256)>) -> Vec<BytesN<32>> {
+        vec![env, BytesN::from_array(env, &[byte; 32]), hash_access_list(env, acl)]
+    }
+
     fn dummy_proof(env: &Env) -> ZKProof {
         ZKProof {
             a: BytesN::from_array(env, &[0u8; 64]),
@@ -312,7 +708,8 @@ mod test {
         let contract_id = env.register(ZKAuth, ());
         let client = ZKAuthClient::new(&env, &contract_id);
         let admin = Address::generate(&env);
-        client.initialize(&admin, &dummy_vk(&env));
+        let admins = vec![&env, admin.clone()];
+        client.initialize(&admins, &1u32, &dummy_vk(&env));
         (env, client, admin)
     }
 
@@ -321,22 +718,41 @@ mod test {
         let (env, client, _admin) = setup();
         let user = Address::generate(&env);
         let agent_pubkey = BytesN::from_array(&env, &[42u8; 32]);
-        let hash = BytesN::from_array(&env, &[7u8; 32]);
+        let hash = single_input(&env, 7, &empty_acl(&env));
 
-        client.start_session(&user, &agent_pubkey, &hash, &1000u32, &dummy_proof(&env));
+        client.start_session(&user, &agent_pubkey, &hash, &empty_acl(&env), &1000u32, &dummy_proof(&env));
 
         assert!(client.is_session_valid(&user));
         assert_eq!(client.get_agent_pubkey(&user), Some(agent_pubkey));
     }
 
+    #[test]
+    fn test_session_accepts_multiple_public_inputs() {
+        let (env, client, _admin) = setup();
+        let user = Address::generate(&env);
+        let agent_pubkey = BytesN::from_array(&env, &[42u8; 32]);
+        let acl = empty_acl(&env);
+        let inputs = vec![
+            &env,
+            BytesN::from_array(&env, &[1u8; 32]),
+            BytesN::from_array(&env, &[2u8; 32]),
+            BytesN::from_array(&env, &[3u8; 32]),
+            hash_access_list(&env, &acl),
+        ];
+
+        client.start_session(&user, &agent_pubkey, &inputs, &acl, &1000u32, &dummy_proof(&env));
+
+        assert!(client.is_session_valid(&user));
+    }
+
     #[test]
     fn test_session_expiry() {
         let (env, client, _admin) = setup();
         let user = Address::generate(&env);
         let agent_pubkey = BytesN::from_array(&env, &[42u8; 32]);
-        let hash = BytesN::from_array(&env, &[7u8; 32]);
+        let hash = single_input(&env, 7, &empty_acl(&env));
 
-        client.start_session(&user, &agent_pubkey, &hash, &720u32, &dummy_proof(&env));
+        client.start_session(&user, &agent_pubkey, &hash, &empty_acl(&env), &720u32, &dummy_proof(&env));
         assert!(client.is_session_valid(&user));
 
         // Advance ledger past expiry
@@ -351,12 +767,12 @@ mod test {
         let user = Address::generate(&env);
         let pubkey1 = BytesN::from_array(&env, &[1u8; 32]);
         let pubkey2 = BytesN::from_array(&env, &[2u8; 32]);
-        let hash = BytesN::from_array(&env, &[7u8; 32]);
+        let hash = single_input(&env, 7, &empty_acl(&env));
 
-        client.start_session(&user, &pubkey1, &hash, &1000u32, &dummy_proof(&env));
+        client.start_session(&user, &pubkey1, &hash, &empty_acl(&env), &1000u32, &dummy_proof(&env));
         assert_eq!(client.get_agent_pubkey(&user), Some(pubkey1));
 
-        client.start_session(&user, &pubkey2, &hash, &2000u32, &dummy_proof(&env));
+        client.start_session(&user, &pubkey2, &hash, &empty_acl(&env), &2000u32, &dummy_proof(&env));
         assert_eq!(client.get_agent_pubkey(&user), Some(pubkey2));
     }
 
@@ -365,9 +781,9 @@ mod test {
         let (env, client, _admin) = setup();
         let user = Address::generate(&env);
         let agent_pubkey = BytesN::from_array(&env, &[42u8; 32]);
-        let hash = BytesN::from_array(&env, &[7u8; 32]);
+        let hash = single_input(&env, 7, &empty_acl(&env));
 
-        client.start_session(&user, &agent_pubkey, &hash, &1000u32, &dummy_proof(&env));
+        client.start_session(&user, &agent_pubkey, &hash, &empty_acl(&env), &1000u32, &dummy_proof(&env));
         assert!(client.is_session_valid(&user));
 
         client.invalidate_session(&user);
@@ -380,9 +796,9 @@ mod test {
         let (env, client, _admin) = setup();
         let user = Address::generate(&env);
         let agent_pubkey = BytesN::from_array(&env, &[42u8; 32]);
-        let hash = BytesN::from_array(&env, &[7u8; 32]);
+        let hash = single_input(&env, 7, &empty_acl(&env));
 
-        client.start_session(&user, &agent_pubkey, &hash, &MIN_SESSION_LEDGERS, &dummy_proof(&env));
+        client.start_session(&user, &agent_pubkey, &hash, &empty_acl(&env), &MIN_SESSION_LEDGERS, &dummy_proof(&env));
 
         // Still valid
         env.ledger().set_sequence_number(env.ledger().sequence() + MIN_SESSION_LEDGERS - 1);
@@ -401,7 +817,8 @@ mod test {
         client.start_session(
             &user,
             &BytesN::from_array(&env, &[1u8; 32]),
-            &BytesN::from_array(&env, &[1u8; 32]),
+            &single_input(&env, 1, &empty_acl(&env)),
+            &empty_acl(&env),
             &100u32,
             &dummy_proof(&env),
         );
@@ -415,7 +832,8 @@ mod test {
         client.start_session(
             &user,
             &BytesN::from_array(&env, &[1u8; 32]),
-            &BytesN::from_array(&env, &[1u8; 32]),
+            &single_input(&env, 1, &empty_acl(&env)),
+            &empty_acl(&env),
             &20000u32,
             &dummy_proof(&env),
         );
@@ -425,6 +843,277 @@ mod test {
     #[should_panic(expected = "AlreadyInitialized")]
     fn test_double_initialize() {
         let (env, client, admin) = setup();
-        client.initialize(&admin, &dummy_vk(&env));
+        client.initialize(&vec![&env, admin], &1u32, &dummy_vk(&env));
+    }
+
+    #[test]
+    fn test_rotate_verifying_key_version() {
+        let (env, client, admin) = setup();
+        let user = Address::generate(&env);
+        let agent_pubkey = BytesN::from_array(&env, &[42u8; 32]);
+        let hash = single_input(&env, 7, &empty_acl(&env));
+
+        client.propose_admin_action(&admin, &AdminAction::RegisterVerifyingKey(2u32, dummy_vk(&env)));
+        client.propose_admin_action(&admin, &AdminAction::SetActiveVkVersion(2u32));
+
+        client.start_session(&user, &agent_pubkey, &hash, &empty_acl(&env), &1000u32, &dummy_proof(&env));
+        assert!(client.is_session_valid(&user));
+    }
+
+    #[test]
+    #[should_panic(expected = "VKVersionNotFound")]
+    fn test_set_active_vk_version_unregistered() {
+        let (env, client, admin) = setup();
+        client.propose_admin_action(&admin, &AdminAction::SetActiveVkVersion(99u32));
+    }
+
+    #[test]
+    fn test_retired_vk_version_invalidates_in_flight_sessions() {
+        let (env, client, admin) = setup();
+        let user = Address::generate(&env);
+        let agent_pubkey = BytesN::from_array(&env, &[42u8; 32]);
+        let hash = single_input(&env, 7, &empty_acl(&env));
+
+        client.start_session(&user, &agent_pubkey, &hash, &empty_acl(&env), &1000u32, &dummy_proof(&env));
+        assert!(client.is_session_valid(&user));
+
+        client.propose_admin_action(&admin, &AdminAction::RetireVkVersion(1u32));
+        assert!(!client.is_session_valid(&user));
+        assert_eq!(client.get_agent_pubkey(&user), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "NotAdmin")]
+    fn test_propose_admin_action_requires_admin() {
+        let (env, client, _admin) = setup();
+        let outsider = Address::generate(&env);
+        client.propose_admin_action(&outsider, &AdminAction::RetireVkVersion(1u32));
+    }
+
+    #[test]
+    fn test_multi_admin_threshold_requires_all_approvals() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(ZKAuth, ());
+        let client = ZKAuthClient::new(&env, &contract_id);
+        let admin_a = Address::generate(&env);
+        let admin_b = Address::generate(&env);
+        client.initialize(&vec![&env, admin_a.clone(), admin_b.clone()], &2u32, &dummy_vk(&env));
+
+        let user = Address::generate(&env);
+        let agent_pubkey = BytesN::from_array(&env, &[42u8; 32]);
+        let hash = single_input(&env, 7, &empty_acl(&env));
+        client.start_session(&user, &agent_pubkey, &hash, &empty_acl(&env), &1000u32, &dummy_proof(&env));
+
+        let action_hash =
+            client.propose_admin_action(&admin_a, &AdminAction::RetireVkVersion(1u32));
+        // One approval short of the 2-of-2 threshold — not yet applied.
+        assert!(client.is_session_valid(&user));
+
+        client.approve_admin_action(&admin_b, &action_hash);
+        assert!(!client.is_session_valid(&user));
+    }
+
+    #[test]
+    #[should_panic(expected = "AlreadyApproved")]
+    fn test_approve_admin_action_rejects_duplicate_approval() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(ZKAuth, ());
+        let client = ZKAuthClient::new(&env, &contract_id);
+        let admin_a = Address::generate(&env);
+        let admin_b = Address::generate(&env);
+        client.initialize(&vec![&env, admin_a.clone(), admin_b], &2u32, &dummy_vk(&env));
+
+        let action_hash =
+            client.propose_admin_action(&admin_a, &AdminAction::RetireVkVersion(1u32));
+        client.approve_admin_action(&admin_a, &action_hash);
+    }
+
+    #[test]
+    fn test_rotate_admin_set_is_governable() {
+        let (env, client, admin) = setup();
+        let new_admin = Address::generate(&env);
+
+        client.propose_admin_action(
+            &admin,
+            &AdminAction::RotateAdminSet(vec![&env, new_admin.clone()], 1u32),
+        );
+
+        // The old admin has been replaced; only the new one may act now.
+        client.propose_admin_action(&new_admin, &AdminAction::RetireVkVersion(1u32));
+    }
+
+    #[test]
+    fn test_pending_action_does_not_execute_on_stale_plus_new_admin_approvals() {
+        // Regression test: an action proposed before a committee rotation must
+        // not be executable by combining its stale pre-rotation approval with
+        // a single post-rotation admin's approval — quorum must be counted
+        // against the *current* AdminSet only.
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(ZKAuth, ());
+        let client = ZKAuthClient::new(&env, &contract_id);
+        let admin_a = Address::generate(&env);
+        let admin_b = Address::generate(&env);
+        client.initialize(&vec![&env, admin_a.clone(), admin_b.clone()], &2u32, &dummy_vk(&env));
+
+        // A proposes (auto-approves) retiring VK version 1; one approval shy
+        // of the 2-of-2 threshold among [A, B].
+        let stale_action_hash =
+            client.propose_admin_action(&admin_a, &AdminAction::RetireVkVersion(1u32));
+
+        // The committee is then legitimately and fully rotated to [D, E],
+        // still 2-of-2 — both current admins approve.
+        let admin_d = Address::generate(&env);
+        let admin_e = Address::generate(&env);
+        let rotate_hash = client.propose_admin_action(
+            &admin_a,
+            &AdminAction::RotateAdminSet(vec![&env, admin_d.clone(), admin_e.clone()], 2u32),
+        );
+        client.approve_admin_action(&admin_b, &rotate_hash);
+
+        // D alone approves the pre-rotation action. A's stale approval must
+        // no longer count towards quorum, so this must NOT execute it.
+        client.approve_admin_action(&admin_d, &stale_action_hash);
+
+        assert!(!env
+            .storage()
+            .instance()
+            .has(&DataKey::RetiredVKVersion(1u32)));
+    }
+
+    #[test]
+    fn test_authorize_action_increments_nonce() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let (env, client, _admin) = setup();
+        let user = Address::generate(&env);
+        let signing_key = SigningKey::from_bytes(&[9u8; 32]);
+        let agent_pubkey = BytesN::from_array(&env, signing_key.verifying_key().as_bytes());
+        let session_hash = single_input(&env, 7, &empty_acl(&env));
+        let action_hash = BytesN::from_array(&env, &[5u8; 32]);
+
+        client.start_session(&user, &agent_pubkey, &session_hash, &empty_acl(&env), &1000u32, &dummy_proof(&env));
+
+        let sign_message = |session_id: u64, nonce: u64| -> [u8; 48] {
+            let mut message = [0u8; 48];
+            message[0..8].copy_from_slice(&session_id.to_be_bytes());
+            message[8..16].copy_from_slice(&nonce.to_be_bytes());
+            message[16..48].copy_from_slice(&action_hash.to_array());
+            message
+        };
+
+        let sig1 = signing_key.sign(&sign_message(1, 0));
+        let new_nonce = client.authorize_action(
+            &user,
+            &action_hash,
+            &BytesN::from_array(&env, &sig1.to_bytes()),
+        );
+        assert_eq!(new_nonce, 1u64);
+
+        // The next authorization must sign over the advanced nonce (1); it is
+        // accepted and bumps the stored nonce to 2.
+        let sig2 = signing_key.sign(&sign_message(1, 1));
+        let new_nonce2 = client.authorize_action(
+            &user,
+            &action_hash,
+            &BytesN::from_array(&env, &sig2.to_bytes()),
+        );
+        assert_eq!(new_nonce2, 2u64);
+    }
+
+    #[test]
+    #[should_panic(expected = "NoActiveSession")]
+    fn test_authorize_action_no_session() {
+        let (env, client, _admin) = setup();
+        let user = Address::generate(&env);
+        client.authorize_action(
+            &user,
+            &BytesN::from_array(&env, &[1u8; 32]),
+            &BytesN::from_array(&env, &[2u8; 64]),
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_authorize_action_bad_signature_rejected() {
+        let (env, client, _admin) = setup();
+        let user = Address::generate(&env);
+        let agent_pubkey = BytesN::from_array(&env, &[42u8; 32]);
+        let hash = single_input(&env, 7, &empty_acl(&env));
+        client.start_session(&user, &agent_pubkey, &hash, &empty_acl(&env), &1000u32, &dummy_proof(&env));
+
+        // Garbage signature bytes never verify against agent_pubkey.
+        client.authorize_action(
+            &user,
+            &BytesN::from_array(&env, &[5u8; 32]),
+            &BytesN::from_array(&env, &[9u8; 64]),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidAgentSignature")]
+    fn test_authorize_action_rejects_all_zero_signature() {
+        // The all-zero signature is a degenerate sentinel we can reject
+        // ourselves with a typed error, unlike a well-formed-but-wrong
+        // signature, which can only be rejected by the host's own
+        // `ed25519_verify` trap (see the comment in `authorize_action`).
+        let (env, client, _admin) = setup();
+        let user = Address::generate(&env);
+        let agent_pubkey = BytesN::from_array(&env, &[42u8; 32]);
+        let hash = single_input(&env, 7, &empty_acl(&env));
+        client.start_session(&user, &agent_pubkey, &hash, &empty_acl(&env), &1000u32, &dummy_proof(&env));
+
+        client.authorize_action(
+            &user,
+            &BytesN::from_array(&env, &[5u8; 32]),
+            &BytesN::from_array(&env, &[0u8; 64]),
+        );
+    }
+
+    #[test]
+    fn test_check_and_consume_spends_delegated_cap() {
+        let (env, client, _admin) = setup();
+        let user = Address::generate(&env);
+        let agent_pubkey = BytesN::from_array(&env, &[42u8; 32]);
+        let vault = Address::generate(&env);
+        let other_contract = Address::generate(&env);
+
+        let mut acl = Vec::new(&env);
+        acl.push_back((vault.clone(), U256::from_u32(&env, 1000)));
+        let hash = single_input(&env, 7, &acl);
+        client.start_session(&user, &agent_pubkey, &hash, &acl, &1000u32, &dummy_proof(&env));
+
+        assert_eq!(
+            client.get_allowance(&user, &vault),
+            Some(U256::from_u32(&env, 1000))
+        );
+        assert_eq!(client.get_allowance(&user, &other_contract), None);
+
+        assert!(client.check_and_consume(&user, &vault, &U256::from_u32(&env, 400)));
+        assert_eq!(
+            client.get_allowance(&user, &vault),
+            Some(U256::from_u32(&env, 600))
+        );
+
+        // Target outside the access list is always refused.
+        assert!(!client.check_and_consume(&user, &other_contract, &U256::from_u32(&env, 1)));
+
+        // Exceeding the remaining cap is refused without mutating it.
+        assert!(!client.check_and_consume(&user, &vault, &U256::from_u32(&env, 601)));
+        assert_eq!(
+            client.get_allowance(&user, &vault),
+            Some(U256::from_u32(&env, 600))
+        );
+    }
+
+    #[test]
+    fn test_check_and_consume_false_without_session() {
+        let (env, client, _admin) = setup();
+        let user = Address::generate(&env);
+        let vault = Address::generate(&env);
+        assert!(!client.check_and_consume(&user, &vault, &U256::from_u32(&env, 1)));
+        assert_eq!(client.get_allowance(&user, &vault), None);
     }
 }